@@ -22,6 +22,8 @@ where
             if cmp_counter >= threshold {
                 debug!("insertion sort comparison threashold reached (threshold={}, where #items={}), so data probably isn't nearly sorted; fallback to standard library's sort", threshold, n);
 
+                // put the target back into the hole left by shifting, before handing over the slice
+                slice[j] = target;
                 slice.sort_by(comparator);
                 return;
             }
@@ -76,4 +78,13 @@ mod insertion_sort_tests {
         let result = [1, 1, 1, 2, 2, 2, 2, 4, 5, 6, 7, 23, 24];
         assert_eq!(a, result);
     }
+
+    #[test]
+    fn fallback_keeps_all_items() {
+        let mut a = [8, 7, 6, 5, 4, 3, 2, 1];
+        insertion_sort(&mut a, |a, b| a.cmp(b));
+
+        let result = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(a, result);
+    }
 }
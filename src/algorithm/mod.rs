@@ -1 +1,2 @@
-pub mod insertion_sort;
\ No newline at end of file
+pub mod insertion_sort;
+pub mod shortest_path;
\ No newline at end of file
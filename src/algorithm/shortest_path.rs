@@ -0,0 +1,74 @@
+use ordered_float::OrderedFloat;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// A* search over a graph of `num_vertices` vertices; `edges` lists the neighbours of a vertex
+/// with the cost of getting there, and `heuristic` must never overestimate the cost from a vertex
+/// to the goal (a heuristic of 0 makes it Dijkstra's algorithm).
+///
+/// Returns the vertices from start to goal, both included, and the total cost; None if the goal
+/// can't be reached. Ties are broken by vertex index, so that the path is deterministic.
+pub fn a_star<E, H>(
+    num_vertices: usize,
+    start: usize,
+    goal: usize,
+    edges: E,
+    heuristic: H,
+) -> Option<(Vec<usize>, f32)>
+where
+    E: Fn(usize) -> Vec<(usize, f32)>,
+    H: Fn(usize) -> f32,
+{
+    let mut costs = vec![f32::INFINITY; num_vertices];
+    let mut previous = vec![None; num_vertices];
+    let mut open = BinaryHeap::new();
+
+    costs[start] = 0.;
+    open.push(Reverse((OrderedFloat(heuristic(start)), start)));
+
+    while let Some(Reverse((_, vertex))) = open.pop() {
+        if vertex == goal {
+            let mut path = vec![goal];
+            while let Some(vertex) = previous[*path.last().unwrap()] {
+                path.push(vertex);
+            }
+            path.reverse();
+            return Some((path, costs[goal]));
+        }
+
+        for (next, cost) in edges(vertex) {
+            let cost = costs[vertex] + cost;
+            if cost < costs[next] {
+                costs[next] = cost;
+                previous[next] = Some(vertex);
+                open.push(Reverse((OrderedFloat(cost + heuristic(next)), next)));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod shortest_path_tests {
+    use super::*;
+
+    // 0 -1- 1 -1- 2
+    //  \         /
+    //   ----5----    3 (unreachable)
+    fn edges(vertex: usize) -> Vec<(usize, f32)> {
+        match vertex {
+            0 => vec![(1, 1.), (2, 5.)],
+            1 => vec![(0, 1.), (2, 1.)],
+            2 => vec![(1, 1.), (0, 5.)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn cheapest_path() {
+        assert_eq!(a_star(4, 0, 2, edges, |_| 0.), Some((vec![0, 1, 2], 2.)));
+        assert_eq!(a_star(4, 2, 2, edges, |_| 0.), Some((vec![2], 0.)));
+        assert_eq!(a_star(4, 0, 3, edges, |_| 0.), None);
+    }
+}
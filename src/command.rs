@@ -1,38 +1,134 @@
-use crate::game::CharacterMobility;
-use crate::game::EntityId;
-use crate::game::Game;
-use crate::game::MovableUnit;
-use crate::geometry::rectangle::Rectangle;
+use crate::parser::{self, Command};
 use nalgebra::Point2;
-use rand::Rng;
-use regex::Regex;
+use new::game::building::BuildingKind;
+use new::game::money::Account;
+use new::game::order::Order;
+use new::game::warehouse::WarehouseSetting;
+use new::game::BuildingId;
+use new::game::CharacterMobility;
+use new::game::Commodity;
+use new::game::Game;
+use new::game::Institution;
+use new::game::MovableUnit;
+
+/// run a command; returns true if the game should exit, or why the command was rejected
+pub fn execute(g: &mut Game, command: Command) -> Result<bool, String> {
+    match command {
+        Command::Help => help(),
+        Command::Exit => return Ok(true),
+        Command::Step { count } => step(g, count),
+        Command::ListNodes => list_nodes(g),
+        Command::ListUnits => list_units(g),
+        Command::ListCharacters => list_characters(g),
+        Command::ListNations => list_nations(g),
+        Command::ListCorporations => list_corporations(g),
+        Command::ListRoads => list_roads(g),
+        Command::ListCollisions => list_collisions(g),
+        Command::MoveUnit {
+            character_idx,
+            node_idx,
+        } => order_unit(g, character_idx, Order::Node(node_idx), false)?,
+        Command::MoveUnitToPoint {
+            character_idx,
+            x,
+            y,
+        } => order_unit(g, character_idx, Order::Point(Point2::new(x, y)), false)?,
+        Command::QueueUnit {
+            character_idx,
+            node_idx,
+        } => order_unit(g, character_idx, Order::Node(node_idx), true)?,
+        Command::QueueUnitToPoint {
+            character_idx,
+            x,
+            y,
+        } => order_unit(g, character_idx, Order::Point(Point2::new(x, y)), true)?,
+        Command::PatrolUnit { character_idx } => patrol_unit(g, character_idx)?,
+        Command::HoldUnit { character_idx } => hold_unit(g, character_idx)?,
+        Command::EstimateNearbyObjects { x, y, radius } => estimate_nearby_objects(g, x, y, radius),
+        Command::CreateBuilding { kind, x, y } => create_building(g, &kind, x, y)?,
+        Command::CreateIndustry { recipe, x, y } => create_industry(g, &recipe, x, y)?,
+        Command::SetRecipe { building, recipe } => set_recipe(g, &building, &recipe)?,
+        Command::DemolishAt { x, y } => demolish_at(g, x, y)?,
+        Command::Demolish { building } => demolish(g, &building)?,
+        Command::ConfigureWarehouse {
+            building,
+            commodity,
+            setting,
+        } => configure_warehouse(g, &building, &commodity, setting)?,
+        Command::ShowWarehouse { building } => show_warehouse(g, &building)?,
+        Command::ShowBuilding { building } => show_building(g, &building)?,
+        Command::ShowMarket { node_idx } => show_market(g, node_idx)?,
+        Command::SetBudget {
+            institution,
+            node_idx,
+            rate,
+        } => set_budget(g, &institution, node_idx, rate)?,
+        Command::ShowNode { node_idx } => show_node(g, node_idx)?,
+        Command::ShowHousing { node_idx } => show_housing(g, node_idx)?,
+        Command::ShowRoute { character_idx } => show_route(g, character_idx)?,
+        Command::BuildBusiness {
+            character_idx,
+            kind,
+            x,
+            y,
+        } => build_business(g, character_idx, &kind, x, y)?,
+        Command::ShowCorporation { corporation_idx } => show_corporation(g, corporation_idx)?,
+        Command::IssueShares {
+            corporation_idx,
+            qty,
+        } => issue_shares(g, corporation_idx, qty)?,
+        Command::TransferShares {
+            corporation_idx,
+            from,
+            to,
+            qty,
+        } => transfer_shares(g, corporation_idx, from, to, qty)?,
+        Command::Audit => audit(g)?,
+        Command::AuditAccount { account } => audit_account(g, &account)?,
+        Command::ListRecipes => list_recipes(g),
+        Command::ListBuildingKinds => list_building_kinds(),
+        Command::LandColonyShip => land_colony_ship(g)?,
+        Command::DeployCharacter { character_idx } => deploy_character(g, character_idx)?,
+        Command::CharacterEnterCity { character_idx } => character_enter_city(g, character_idx)?,
+        Command::Save { path } => save(g, &path)?,
+        Command::Load { path } => load(g, &path)?,
+    }
+    Ok(false)
+}
 
-pub fn exit(_: &mut Game, _: &Regex, _: &str) -> bool {
-    true
+pub fn help() {
+    print!("{}", parser::help());
 }
 
-pub fn step(g: &mut Game, _: &Regex, _: &str) -> bool {
-    g.update_state();
-    debug!("step");
-    false
+pub fn step(g: &mut Game, count: usize) {
+    for _ in 0..count {
+        g.update_state();
+    }
+    debug!("step {}", count);
 }
 
-pub fn list_nodes(g: &mut Game, _: &Regex, _: &str) -> bool {
-    println!("{:>16} {:>16}", "index", "coor");
+pub fn list_nodes(g: &mut Game) {
+    println!(
+        "{:>16} {:>16} {:>16} {:>16} {:>16} {:>16}",
+        "index", "coor", "population", "births", "deaths", "food"
+    );
     for (i, node) in g.nodes.iter().enumerate() {
         println!(
-            "{:>16} {:>16}",
+            "{:>16} {:>16} {:>16} {:>16} {:>16} {:>16}",
             i,
-            format!("({},{})", node.coor[0] as u32, node.coor[1] as u32)
+            format!("({},{})", node.coor[0] as u32, node.coor[1] as u32),
+            node.population,
+            node.births,
+            node.deaths,
+            node.food_supply()
         );
     }
-    false
 }
 
-pub fn list_units(g: &mut Game, _: &Regex, _: &str) -> bool {
+pub fn list_units(g: &mut Game) {
     println!(
-        "{:>16} {:>32} {:>16} {:>32}",
-        "char id", "coor", "speed", "goal"
+        "{:>16} {:>32} {:>16} {:>16}   orders",
+        "char id", "coor", "speed", "heading"
     );
     for (character_idx, character) in g.entities.characters.iter().enumerate() {
         if let CharacterMobility::Unit { body, .. } = character.mobility {
@@ -40,170 +136,511 @@ pub fn list_units(g: &mut Game, _: &Regex, _: &str) -> bool {
             let unit_x = coor.x;
             let unit_y = coor.y;
             println!(
-                "{:>16} {:>32} {:>16} {:>32}",
+                "{:>16} {:>32} {:>16} {:>16.0}   {}",
                 character_idx,
                 format!("({:.2},{:.2})", unit_x, unit_y),
                 character.cal_speed(),
-                match g.unit_destinations.get(&character_idx) {
-                    Some(destination) => {
-                        //
-                        let coor = g.nodes[*destination].coor;
-                        format!(
-                            "{} - {:>20}",
-                            destination,
-                            format!("({:.2},{:.2})", coor.x, coor.y)
-                        )
+                body.angle.to_degrees(),
+                match g.unit_orders.get(&character_idx) {
+                    Some(orders) => {
+                        let queue: Vec<_> = orders.queue.iter().map(Order::to_string).collect();
+                        let mut queue = queue.join(", ");
+                        if orders.unreachable_from.is_some() {
+                            queue.push_str(" (no route)");
+                        }
+                        if orders.patrol {
+                            format!("patrol {}", queue)
+                        } else {
+                            queue
+                        }
                     }
-                    None => "-".to_string(),
+                    None => "hold".to_string(),
                 }
             );
         } else {
             //
         }
     }
-    false
 }
 
-pub fn move_unit(g: &mut Game, regex: &Regex, line: &str) -> bool {
-    let group = regex.captures(line).unwrap();
+pub fn order_unit(
+    g: &mut Game,
+    character_idx: usize,
+    order: Order,
+    queued: bool,
+) -> Result<(), String> {
+    if queued {
+        println!("character {} will then move to {}", character_idx, order);
+    } else {
+        println!("trying to move character {} to {}", character_idx, order);
+    }
+    g.order_unit(character_idx, order, queued)
+        .map_err(|err| format!("cannot move unit: {}", err))
+}
 
-    let character_idx = group[1].parse().unwrap();
-    let node_idx = group[2].parse().unwrap();
+pub fn patrol_unit(g: &mut Game, character_idx: usize) -> Result<(), String> {
+    g.patrol_unit(character_idx)
+        .map_err(|err| format!("cannot patrol: {}", err))
+}
 
-    println!(
-        "trying to move character {} to node {}",
-        character_idx, node_idx
-    );
-    g.set_unit_destination(character_idx, node_idx);
+pub fn hold_unit(g: &mut Game, character_idx: usize) -> Result<(), String> {
+    g.hold_unit(character_idx)
+        .map_err(|err| format!("cannot hold: {}", err))
+}
 
-    false
+pub fn deploy_character(g: &mut Game, character_idx: usize) -> Result<(), String> {
+    g.deploy_character(character_idx)
+        .map_err(|err| format!("cannot deploy character: {}", err))
 }
 
-pub fn deploy_character(g: &mut Game, regex: &Regex, line: &str) -> bool {
-    let group = regex.captures(line).unwrap();
+pub fn estimate_nearby_objects(g: &mut Game, x: f32, y: f32, r: f32) {
+    // sorted, so that the output doesn't depend on the hash set's order
+    let mut nearby: Vec<_> = g
+        .estimate_nearby_units(Point2::new(x, y), r)
+        .into_iter()
+        .collect();
+    nearby.sort_unstable();
+    for idx in nearby {
+        match g.get_unit_coor(idx) {
+            Ok(coor) => println!("{:?} - {}", idx, coor),
+            Err(err) => println!("{:?} - {}", idx, err),
+        }
+    }
+}
 
-    let character_idx: usize = group[1].parse().unwrap();
+pub fn character_enter_city(g: &mut Game, character_idx: usize) -> Result<(), String> {
+    // TODO
 
-    if character_idx >= g.entities.characters.len() {
-        println!("invalid character id");
-        return false;
-    }
+    g.deploy_character(character_idx)
+        .map_err(|err| format!("cannot deploy character: {}", err))
+}
 
-    let character = &mut g.entities.characters[character_idx];
+pub fn create_building(g: &mut Game, kind: &str, x: u32, y: u32) -> Result<(), String> {
+    let building_kind = BuildingKind::from_name(kind).ok_or_else(|| {
+        let names: Vec<_> = BuildingKind::ALL
+            .iter()
+            .map(|kind| kind.spec().name)
+            .collect();
+        format!(
+            "cannot create {}: expected one of {}",
+            kind,
+            names.join(", ")
+        )
+    })?;
+    let id = g
+        .create_building(building_kind, [x, y])
+        .map_err(|err| format!("cannot create {} at ({},{}): {}", kind, x, y, err))?;
+    println!("created {:?} at ({},{})", id, x, y);
+    Ok(())
+}
 
-    if let CharacterMobility::Parked { node_idx } = character.mobility {
-        let coor = g.nodes[node_idx].coor;
-        let unit = CharacterMobility::Unit {
-            body: Rectangle {
-                length: 1.,
-                width: 1.,
-                center: coor,
-                angle: 0.,
-            },
-            character_idx,
-        };
+pub fn create_industry(g: &mut Game, recipe: &str, x: u32, y: u32) -> Result<(), String> {
+    let id = g.create_industry([x, y], recipe).map_err(|err| {
+        format!(
+            "cannot create {} industry at ({},{}): {}",
+            recipe, x, y, err
+        )
+    })?;
+    println!("created {:?} at ({},{})", id, x, y);
+    Ok(())
+}
 
-        character.mobility = unit;
+pub fn demolish_at(g: &mut Game, x: u32, y: u32) -> Result<(), String> {
+    let id = g
+        .demolish_at((x, y))
+        .map_err(|err| format!("cannot demolish at ({},{}): {}", x, y, err))?;
+    println!("demolished {:?}", id);
+    Ok(())
+}
 
-        g.unit_x_axis.push(character_idx);
-        g.unit_y_axis.push(character_idx);
-    } else {
-        debug!("character isn't parked in a city");
-        return false;
-    }
+pub fn demolish(g: &mut Game, building: &str) -> Result<(), String> {
+    let building_id = BuildingId::from_name(building)
+        .ok_or_else(|| format!("cannot demolish: {} isn't a building id", building))?;
+    g.demolish(building_id)
+        .map_err(|err| format!("cannot demolish: {}", err))?;
+    println!("demolished {:?}", building_id);
+    Ok(())
+}
+
+fn parse_building_id(building: &str) -> Result<BuildingId, String> {
+    BuildingId::from_name(building).ok_or_else(|| format!("{} isn't a building id", building))
+}
+
+fn parse_commodity(commodity: &str) -> Result<Commodity, String> {
+    Commodity::from_name(commodity).ok_or_else(|| format!("no commodity {}", commodity))
+}
 
-    false
+pub fn configure_warehouse(
+    g: &mut Game,
+    building: &str,
+    commodity: &str,
+    setting: WarehouseSetting,
+) -> Result<(), String> {
+    let configure = |g: &mut Game| -> Result<(), String> {
+        let building_id = parse_building_id(building)?;
+        let commodity = parse_commodity(commodity)?;
+        g.configure_warehouse(building_id, commodity, setting)
+            .map_err(|err| err.to_string())
+    };
+    configure(g).map_err(|err| format!("cannot configure warehouse: {}", err))
 }
 
-pub fn estimate_nearby_objects(g: &mut Game, regex: &Regex, line: &str) -> bool {
-    let group = regex.captures(&line).unwrap();
+pub fn show_warehouse(g: &mut Game, building: &str) -> Result<(), String> {
+    let building_id =
+        parse_building_id(building).map_err(|err| format!("cannot show warehouse: {}", err))?;
+    let warehouse = g
+        .warehouse(building_id)
+        .map_err(|err| format!("cannot show warehouse: {}", err))?;
+    let node = g
+        .node_of_building(building_id)
+        .map(|node_idx| &g.nodes[node_idx]);
 
-    let x = group[1].parse().unwrap();
-    let y = group[2].parse().unwrap();
-    let r = group[3].parse().unwrap();
+    println!(
+        "balance: {}",
+        g.ledger.balance(Account::Building(building_id))
+    );
+    println!(
+        "{:>16} {:>16} {:>16} {:>16} {:>16} {:>16}",
+        "commodity", "storage", "demand", "import price", "sale price", "market price"
+    );
+    for commodity in Commodity::all() {
+        let is_traded = warehouse.storage(commodity) > 0
+            || warehouse.demand_qty(commodity) > 0
+            || warehouse.sale_price(commodity) > 0.;
+        if !is_traded {
+            continue;
+        }
+        println!(
+            "{:>16} {:>16} {:>16} {:>16.2} {:>16.2} {:>16}",
+            format!("{:?}", commodity),
+            warehouse.storage(commodity),
+            warehouse.demand_qty(commodity),
+            warehouse.import_price(commodity),
+            warehouse.sale_price(commodity),
+            match node {
+                Some(node) => format!("{:.2}", node.price(commodity)),
+                None => "-".to_owned(),
+            }
+        );
+    }
 
-    for idx in g.estimate_nearby_units(Point2::new(x, y), r) {
-        let coor = g.get_unit_coor(idx);
-        println!("{:?} - {}", idx, coor);
+    println!(
+        "{:>16} {:>16} {:>16} {:>16}",
+        "tick", "trade", "qty", "total"
+    );
+    for trade in warehouse.ledger() {
+        println!(
+            "{:>16} {:>16} {:>16} {:>16.2}",
+            trade.tick,
+            format!("{:?} {:?}", trade.side, trade.commodity),
+            trade.qty,
+            trade.total
+        );
     }
-    false
+    Ok(())
 }
 
-pub fn character_enter_city(g: &mut Game, regex: &Regex, line: &str) -> bool {
-    // TODO
+pub fn show_building(g: &mut Game, building: &str) -> Result<(), String> {
+    let building_id =
+        parse_building_id(building).map_err(|err| format!("cannot show building: {}", err))?;
+    let node_idx = g
+        .node_of_building(building_id)
+        .ok_or_else(|| format!("cannot show building: no building {:?}", building_id))?;
 
-    let group = regex.captures(line).unwrap();
+    println!(
+        "{:?}: a {} in node {}",
+        building_id,
+        building_id.kind(),
+        node_idx
+    );
+    if !building_id.kind().is_producer() {
+        return Ok(());
+    }
 
-    let character_idx: usize = group[1].parse().unwrap();
+    let bonus = g
+        .output_bonus(building_id)
+        .map_err(|err| format!("cannot show building: {}", err))?;
+    println!("output multiplier: {:.2}", bonus.multiplier());
+    println!("{:>24} {:>16}", "bonus", "value");
+    println!(
+        "{:>24} {:>+16.2}",
+        format!("scale ({} more)", bonus.same_kind),
+        bonus.scale
+    );
+    for (neighbour, value) in bonus.neighbours {
+        println!(
+            "{:>24} {:>+16.2}",
+            format!("next to {:?}", neighbour),
+            value
+        );
+    }
+    Ok(())
+}
+
+pub fn show_market(g: &mut Game, node_idx: usize) -> Result<(), String> {
+    let node = g
+        .nodes
+        .get(node_idx)
+        .ok_or_else(|| format!("cannot show market: no node {}", node_idx))?;
 
-    if character_idx >= g.entities.characters.len() {
-        println!("invalid character id");
-        return false;
+    println!(
+        "closing prices of the last {} ticks",
+        node.price_history.len()
+    );
+    println!(
+        "{:>16} {:>16} {:>16} {:>16} {:>16} {:>16}",
+        "commodity", "stock", "demand", "price", "low", "high"
+    );
+    for commodity in Commodity::all() {
+        if node.market[commodity] == 0 && node.demand[commodity] == 0 {
+            continue;
+        }
+        let price = node.price(commodity);
+        let history = node.price_history.iter().map(|prices| prices[commodity]);
+        let low = history.clone().fold(price, f32::min);
+        let high = history.fold(price, f32::max);
+        println!(
+            "{:>16} {:>16} {:>16} {:>16.2} {:>16.2} {:>16.2}",
+            format!("{:?}", commodity),
+            node.market[commodity],
+            node.demand[commodity],
+            price,
+            low,
+            high
+        );
     }
+    Ok(())
+}
 
-    let character = &mut g.entities.characters[character_idx];
+pub fn set_budget(
+    g: &mut Game,
+    institution: &str,
+    node_idx: usize,
+    rate: f32,
+) -> Result<(), String> {
+    let institution = Institution::from_name(institution)
+        .ok_or_else(|| format!("cannot set budget: no institution {}", institution))?;
+    g.set_budget(node_idx, institution, rate)
+        .map_err(|err| format!("cannot set budget: {}", err))
+}
 
-    if let CharacterMobility::Parked { node_idx } = character.mobility {
-        let coor = g.nodes[node_idx].coor;
-        let unit = CharacterMobility::Unit {
-            body: Rectangle {
-                length: 1.,
-                width: 1.,
-                center: coor,
-                angle: 0.,
-            },
-            character_idx,
-        };
+pub fn show_node(g: &mut Game, node_idx: usize) -> Result<(), String> {
+    let node = g
+        .nodes
+        .get(node_idx)
+        .ok_or_else(|| format!("cannot show node: no node {}", node_idx))?;
+
+    let neighbours = g
+        .neighbouring_nodes(node_idx)
+        .map_err(|err| format!("cannot show node: {}", err))?;
+    let neighbours: Vec<_> = neighbours.iter().map(ToString::to_string).collect();
+
+    println!("neighbours: {}", neighbours.join(", "));
+    println!("population: {}", node.population);
+    println!("crime rate: {:.4}", node.crime_rate());
+    println!("militia: {}", node.militia());
+    println!(
+        "{:>16} {:>16} {:>16} {:>16} {:>16}",
+        "institution", "budget", "capacity", "actual", "effect"
+    );
+    for &institution in &Institution::ALL {
+        let data = &node.institutions[institution];
+        println!(
+            "{:>16} {:>16.2} {:>16} {:>16} {:>16.2}",
+            institution.name(),
+            data.budget_rate(),
+            data.capacity(),
+            data.actual(),
+            data.effect()
+        );
+    }
+    Ok(())
+}
 
-        character.mobility = unit;
+pub fn show_housing(g: &mut Game, node_idx: usize) -> Result<(), String> {
+    let node = g
+        .nodes
+        .get(node_idx)
+        .ok_or_else(|| format!("cannot show housing: no node {}", node_idx))?;
+    if !g.is_colonized(node_idx) {
+        return Err(format!(
+            "cannot show housing: node {} isn't colonized",
+            node_idx
+        ));
+    }
+    let housing = &node.housing;
 
-        g.unit_x_axis.push(character_idx);
-        g.unit_y_axis.push(character_idx);
-    } else {
-        debug!("character isn't parked in a city");
-        return false;
+    println!("population: {}", node.population);
+    println!(
+        "capacity: {} (up to {} overcrowded)",
+        housing.capacity,
+        housing.max_residents()
+    );
+    println!(
+        "housed: {}, overcrowded: {}, homeless: {}",
+        housing.housed, housing.overcrowded, housing.homeless
+    );
+    println!("satisfaction: {:.2}", housing.satisfaction());
+    println!("{:>24} {:>16} {:>16}", "building", "capacity", "residents");
+    for building in node.households() {
+        let residents = g
+            .entities
+            .buildings
+            .household(building)
+            .map_or(0, |household| household.num_people());
+        println!(
+            "{:>24} {:>16} {:>16}",
+            format!("{:?}", building),
+            building.kind().housing_capacity(),
+            residents
+        );
     }
+    Ok(())
+}
 
-    false
+pub fn show_route(g: &mut Game, character_idx: usize) -> Result<(), String> {
+    let coor = g
+        .get_unit_coor(character_idx)
+        .map_err(|err| format!("cannot show route: {}", err))?;
+    let (route, eta) = match g
+        .unit_route(character_idx)
+        .map_err(|err| format!("cannot show route: {}", err))?
+    {
+        Some(route) => route,
+        None => {
+            println!("unit {} has no route", character_idx);
+            return Ok(());
+        }
+    };
+
+    println!(
+        "{:>16} {:>32} {:>16} {:>16}",
+        "node", "coor", "distance", "total"
+    );
+    let mut from = coor;
+    let mut total = 0.;
+    for &node_idx in &route.waypoints {
+        let to = g.nodes[node_idx].coor;
+        let distance = nalgebra::distance(&from, &to);
+        total += distance;
+        println!(
+            "{:>16} {:>32} {:>16.2} {:>16.2}",
+            node_idx,
+            format!("({:.2},{:.2})", to.x, to.y),
+            distance,
+            total
+        );
+        from = to;
+    }
+    println!("arrival in {} ticks", eta);
+    Ok(())
 }
 
-pub fn create_farm(g: &mut Game, regex: &Regex, line: &str) -> bool {
-    let group = regex.captures(&line).unwrap();
+pub fn audit(g: &mut Game) -> Result<(), String> {
+    println!("{:>24} {:>16}", "account", "balance");
+    for (account, balance) in g.ledger.balances() {
+        println!("{:>24} {:>16}", account, balance);
+    }
 
-    let x = group[1].parse().unwrap();
-    let y = group[2].parse().unwrap();
+    g.ledger
+        .audit()
+        .map_err(|err| format!("audit failed: {}", err))?;
+    println!("the ledger is balanced");
+    Ok(())
+}
 
-    if let Some(id) = g.create_farm([x, y]) {
-        println!("created {:?} at ({},{})", id, x, y);
-    } else {
-        println!("cannot create farm at ({},{})", x, y);
+pub fn audit_account(g: &mut Game, account: &str) -> Result<(), String> {
+    let account = Account::from_name(account)
+        .ok_or_else(|| format!("cannot audit: {} isn't an account", account))?;
+
+    println!("balance: {}", g.ledger.balance(account));
+    println!(
+        "{:>16} {:>16} {:>24} {:>16}",
+        "tick", "kind", "counterparty", "amount"
+    );
+    for transaction in g.ledger.journal() {
+        let (counterparty, amount) = if transaction.from == account {
+            (transaction.to, -transaction.amount)
+        } else if transaction.to == account {
+            (transaction.from, transaction.amount)
+        } else {
+            continue;
+        };
+        println!(
+            "{:>16} {:>16} {:>24} {:>16}",
+            transaction.tick,
+            format!("{:?}", transaction.kind),
+            counterparty,
+            amount
+        );
     }
-    false
+    Ok(())
 }
 
-pub fn create_warehouse(g: &mut Game, regex: &Regex, line: &str) -> bool {
-    let group = regex.captures(&line).unwrap();
+pub fn set_recipe(g: &mut Game, building: &str, recipe: &str) -> Result<(), String> {
+    let building_id = BuildingId::from_name(building)
+        .ok_or_else(|| format!("cannot set recipe: {} isn't a building id", building))?;
+    g.set_recipe(building_id, recipe)
+        .map_err(|err| format!("cannot set recipe: {}", err))?;
+    println!("{:?} now makes {}", building_id, recipe);
+    Ok(())
+}
 
-    let x = group[1].parse().unwrap();
-    let y = group[2].parse().unwrap();
+pub fn list_building_kinds() {
+    println!(
+        "{:>24} {:>16} {:>16} {:>16}",
+        "kind", "footprint", "cost", "per city"
+    );
+    for kind in BuildingKind::ALL.iter() {
+        let spec = kind.spec();
+        let (width, height) = spec.footprint;
+        println!(
+            "{:>24} {:>16} {:>16} {:>16}",
+            spec.name,
+            format!("{}x{}", width, height),
+            spec.cost,
+            match spec.per_city_limit {
+                Some(limit) => limit.to_string(),
+                None => "-".to_owned(),
+            }
+        );
+    }
+}
 
-    if let Some(id) = g.create_warehouse([x, y]) {
-        println!("created {:?} at ({},{})", id, x, y);
-    } else {
-        println!("cannot create warehouse at ({},{})", x, y);
+pub fn list_recipes(g: &mut Game) {
+    fn quantities(commodities: &[(Commodity, u32)]) -> String {
+        let quantities: Vec<_> = commodities
+            .iter()
+            .map(|(commodity, qty)| format!("{} {:?}", qty, commodity))
+            .collect();
+        quantities.join(", ")
+    }
+
+    println!(
+        "{:>16} {:>16} {:>8} {:>32} {:>32}",
+        "name", "building", "labor", "inputs", "outputs"
+    );
+    for recipe in g.recipes.iter() {
+        println!(
+            "{:>16} {:>16} {:>8} {:>32} {:>32}",
+            recipe.name,
+            format!("{:?}", recipe.workplace),
+            recipe.labor,
+            quantities(&recipe.inputs),
+            quantities(&recipe.outputs)
+        );
     }
-    false
 }
 
-pub fn land_colony_ship(g: &mut Game, _: &Regex, _: &str) -> bool {
-    let id = g.colonize();
-    assert!(id.is_some());
+pub fn land_colony_ship(g: &mut Game) -> Result<(), String> {
+    let id = g
+        .colonize()
+        .map_err(|err| format!("cannot land colony ship: {}", err))?;
     println!("colony landing building id: {:?}", id);
-
-    false
+    Ok(())
 }
 
-pub fn list_characters(g: &mut Game, _: &Regex, _: &str) -> bool {
+pub fn list_characters(g: &mut Game) {
     println!(
         "{:>16} {:>16} {:>16} {:>16} {:>16} {:>16} {:>30}",
         "id", "strength", "dexterity", "intelligence", "charisma", "# childs", "mobility"
@@ -229,18 +666,151 @@ pub fn list_characters(g: &mut Game, _: &Regex, _: &str) -> bool {
             mobility,
         );
     }
-
-    false
 }
 
-pub fn list_nations(g: &mut Game, _: &Regex, _: &str) -> bool {
-    println!("{:>16} {:>16}", "index", "# cities");
+pub fn list_nations(g: &mut Game) {
+    println!(
+        "{:>16} {:>16} {:>16} {:>16}",
+        "index", "# cities", "treasury", "tech level"
+    );
 
     for (idx, nation) in g.nations.iter().enumerate() {
         let num_cities = nation.cities.len();
         //
-        println!("{:>16} {:>16}", idx, num_cities);
+        let treasury = g.ledger.balance(Account::Nation(idx));
+        println!(
+            "{:>16} {:>16} {:>16} {:>16}",
+            idx,
+            num_cities,
+            treasury,
+            nation.tech_level()
+        );
+    }
+}
+
+pub fn list_roads(g: &mut Game) {
+    println!("{:>16} {:>16} {:>16}", "from", "to", "length");
+
+    for road in g.roads.iter() {
+        println!("{:>16} {:>16} {:>16.1}", road.from, road.to, road.length);
     }
+}
+
+pub fn list_collisions(g: &mut Game) {
+    println!("{:>16} {:>16} {:>16}", "unit", "unit", "depth");
+
+    for collision in g.collisions() {
+        let (a, b) = collision.units;
+        println!("{:>16} {:>16} {:>16.2}", a, b, collision.penetration.norm());
+    }
+}
+
+pub fn list_corporations(g: &mut Game) {
+    println!(
+        "{:>16} {:>16} {:>16} {:>16} {:>16}",
+        "index", "ceo", "# shares", "# buildings", "balance"
+    );
+
+    for (idx, corporation) in g.corporations.iter().enumerate() {
+        println!(
+            "{:>16} {:>16} {:>16} {:>16} {:>16}",
+            idx,
+            corporation.ceo(),
+            corporation.num_issued_shares(),
+            corporation.buildings().len(),
+            g.ledger.balance(Account::Corporation(idx))
+        );
+    }
+}
+
+pub fn build_business(
+    g: &mut Game,
+    character_idx: usize,
+    kind: &str,
+    x: u32,
+    y: u32,
+) -> Result<(), String> {
+    let build = |g: &mut Game| -> Result<BuildingId, String> {
+        let kind = BuildingKind::from_name(kind).ok_or_else(|| format!("no building {}", kind))?;
+        g.build_business(character_idx, kind, [x, y])
+            .map_err(|err| err.to_string())
+    };
+    let id = build(g).map_err(|err| {
+        format!(
+            "character {} cannot build {} at ({},{}): {}",
+            character_idx, kind, x, y, err
+        )
+    })?;
+    let corporation_idx = g
+        .corporation_of_building(id)
+        .expect("bug: businesses belong to a corporation");
+    println!(
+        "created {:?} at ({},{}) for corporation {}",
+        id, x, y, corporation_idx
+    );
+    Ok(())
+}
+
+pub fn show_corporation(g: &mut Game, corporation_idx: usize) -> Result<(), String> {
+    let corporation = g
+        .corporation(corporation_idx)
+        .map_err(|err| format!("cannot show corporation: {}", err))?;
+
+    println!("ceo: character {}", corporation.ceo());
+    println!(
+        "headquarters: {}",
+        match corporation.hq() {
+            Some(hq) => format!("{:?}", hq),
+            None => "-".to_owned(),
+        }
+    );
+    println!(
+        "balance: {}",
+        g.ledger.balance(Account::Corporation(corporation_idx))
+    );
+
+    println!("{:>16} {:>16} {:>16}", "shareholder", "# shares", "share");
+    for (character_idx, shares) in corporation.shareholders() {
+        let percent = 100. * shares as f32 / corporation.num_issued_shares() as f32;
+        println!("{:>16} {:>16} {:>15.1}%", character_idx, shares, percent);
+    }
+
+    println!("{:>16} {:>16}", "building", "balance");
+    for building in corporation.buildings() {
+        println!(
+            "{:>16} {:>16}",
+            format!("{:?}", building),
+            g.ledger.balance(Account::Building(building))
+        );
+    }
+    Ok(())
+}
+
+pub fn issue_shares(g: &mut Game, corporation_idx: usize, qty: u32) -> Result<(), String> {
+    g.issue_shares(corporation_idx, qty)
+        .map_err(|err| format!("cannot issue shares: {}", err))
+}
+
+pub fn transfer_shares(
+    g: &mut Game,
+    corporation_idx: usize,
+    from: usize,
+    to: usize,
+    qty: u32,
+) -> Result<(), String> {
+    g.transfer_shares(corporation_idx, from, to, qty)
+        .map_err(|err| format!("cannot transfer shares: {}", err))
+}
+
+pub fn save(g: &mut Game, path: &str) -> Result<(), String> {
+    g.save(path)
+        .map_err(|err| format!("cannot save game to {}: {}", path, err))?;
+    println!("saved game to {}", path);
+    Ok(())
+}
 
-    false
+pub fn load(g: &mut Game, path: &str) -> Result<(), String> {
+    *g = Game::load(path).map_err(|err| format!("cannot load game from {}: {}", path, err))?;
+    println!("loaded game from {}", path);
+    Ok(())
 }
@@ -0,0 +1,313 @@
+use crate::game::building::BuildingKind;
+use crate::game::BuildingId;
+use crate::game::Game;
+use crate::game::GameError;
+use crate::game::Node;
+use enum_map::EnumMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// the bonuses compiled into the game; see the file for its format
+const BUILTIN_BONUSES: &str = include_str!("../../data/bonuses.toml");
+
+/// output multipliers of farms, mines, workshops and factories
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bonuses {
+    max_scale: f32,
+    scale: EnumMap<BuildingKind, f32>, // per other building of the same kind in the node
+    adjacency: EnumMap<BuildingKind, EnumMap<BuildingKind, f32>>, // building, neighbour
+}
+
+#[derive(Debug)]
+pub enum BonusError {
+    Io(io::Error),
+    Format(toml::de::Error),
+    NotAProducer(BuildingKind),
+    DuplicateScale(BuildingKind),
+    DuplicateAdjacency {
+        building: BuildingKind,
+        neighbour: BuildingKind,
+    },
+}
+
+impl fmt::Display for BonusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BonusError::Io(err) => write!(f, "cannot read bonuses: {}", err),
+            BonusError::Format(err) => write!(f, "invalid bonuses: {}", err),
+            BonusError::NotAProducer(kind) => write!(f, "a {} doesn't produce anything", kind),
+            BonusError::DuplicateScale(kind) => {
+                write!(f, "scale bonus of {} is defined twice", kind)
+            }
+            BonusError::DuplicateAdjacency {
+                building,
+                neighbour,
+            } => write!(
+                f,
+                "adjacency bonus of {} next to {} is defined twice",
+                building, neighbour
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BonusError {}
+
+/// layout of the data file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BonusFile {
+    max_scale: f32,
+    #[serde(default)]
+    scale: Vec<ScaleEntry>,
+    #[serde(default)]
+    adjacency: Vec<AdjacencyEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScaleEntry {
+    building: BuildingKind,
+    bonus: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AdjacencyEntry {
+    building: BuildingKind,
+    neighbour: BuildingKind,
+    bonus: f32,
+}
+
+/// why a building produces more, or less, than its base output
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutputBonus {
+    pub same_kind: usize, // other buildings of the same kind in the node
+    pub scale: f32,
+    pub neighbours: Vec<(BuildingId, f32)>, // touching buildings with a bonus, in order
+}
+
+impl OutputBonus {
+    pub fn multiplier(&self) -> f32 {
+        let adjacency: f32 = self.neighbours.iter().map(|&(_, bonus)| bonus).sum();
+        (1. + self.scale + adjacency).max(0.)
+    }
+}
+
+impl BuildingKind {
+    /// buildings whose output gets bonuses
+    pub fn is_producer(self) -> bool {
+        matches!(
+            self,
+            BuildingKind::Farm
+                | BuildingKind::Mine
+                | BuildingKind::Workshop
+                | BuildingKind::Factory
+        )
+    }
+}
+
+impl Bonuses {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BonusError> {
+        let content = fs::read_to_string(path).map_err(BonusError::Io)?;
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, BonusError> {
+        let file: BonusFile = toml::from_str(content).map_err(BonusError::Format)?;
+
+        let mut bonuses = Self {
+            max_scale: file.max_scale,
+            scale: Default::default(),
+            adjacency: Default::default(),
+        };
+
+        let mut defined = EnumMap::<BuildingKind, bool>::default();
+        for entry in file.scale {
+            if !entry.building.is_producer() {
+                return Err(BonusError::NotAProducer(entry.building));
+            }
+            if std::mem::replace(&mut defined[entry.building], true) {
+                return Err(BonusError::DuplicateScale(entry.building));
+            }
+            bonuses.scale[entry.building] = entry.bonus;
+        }
+
+        let mut defined = EnumMap::<BuildingKind, EnumMap<BuildingKind, bool>>::default();
+        for entry in file.adjacency {
+            if !entry.building.is_producer() {
+                return Err(BonusError::NotAProducer(entry.building));
+            }
+            if std::mem::replace(&mut defined[entry.building][entry.neighbour], true) {
+                return Err(BonusError::DuplicateAdjacency {
+                    building: entry.building,
+                    neighbour: entry.neighbour,
+                });
+            }
+            bonuses.adjacency[entry.building][entry.neighbour] = entry.bonus;
+        }
+
+        Ok(bonuses)
+    }
+}
+
+impl Default for Bonuses {
+    fn default() -> Self {
+        Self::parse(BUILTIN_BONUSES).expect("bug: built-in bonuses are invalid")
+    }
+}
+
+impl Node {
+    /// bonuses of every farm, mine, workshop and factory of the node
+    pub fn output_bonuses(
+        &self,
+        bonuses: &Bonuses,
+        building_grid: &HashMap<(u32, u32), BuildingId>,
+        building_sites: &HashMap<BuildingId, [u32; 2]>,
+    ) -> BTreeMap<BuildingId, OutputBonus> {
+        let mut num_per_kind = EnumMap::<BuildingKind, usize>::default();
+        for building in &self.buildings {
+            num_per_kind[building.kind()] += 1;
+        }
+
+        // buildings touching an edge of each producer, from the tiles of the producer
+        let mut neighbours = BTreeMap::new();
+        for &building in &self.buildings {
+            if !building.kind().is_producer() {
+                continue;
+            }
+            let mut touching = BTreeSet::new();
+            if let Some(&[left, top]) = building_sites.get(&building) {
+                let (width, height) = building.kind().spec().footprint;
+                for x in left..left + width {
+                    for y in top..top + height {
+                        let edges = [
+                            (x.wrapping_sub(1), y),
+                            (x + 1, y),
+                            (x, y.wrapping_sub(1)),
+                            (x, y + 1),
+                        ];
+                        for tile in &edges {
+                            match building_grid.get(tile) {
+                                Some(&other) if other != building => {
+                                    touching.insert(other);
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+            }
+            neighbours.insert(building, touching);
+        }
+
+        neighbours
+            .into_iter()
+            .map(|(building, touching)| {
+                let kind = building.kind();
+                let same_kind = num_per_kind[kind] - 1;
+                let scale = (bonuses.scale[kind] * same_kind as f32).min(bonuses.max_scale);
+                let neighbours = touching
+                    .into_iter()
+                    .map(|other| (other, bonuses.adjacency[kind][other.kind()]))
+                    .filter(|&(_, bonus)| bonus != 0.)
+                    .collect();
+                let bonus = OutputBonus {
+                    same_kind,
+                    scale,
+                    neighbours,
+                };
+                (building, bonus)
+            })
+            .collect()
+    }
+}
+
+impl Game {
+    /// bonuses of a farm, mine, workshop or factory
+    pub fn output_bonus(&self, building: BuildingId) -> Result<OutputBonus, GameError> {
+        if !self.entities.buildings.contains(building) {
+            return Err(GameError::NoSuchBuilding(building));
+        }
+        if !building.kind().is_producer() {
+            return Err(GameError::NotAProducer(building));
+        }
+        let node_idx = self
+            .node_of_building(building)
+            .ok_or(GameError::NoSuchBuilding(building))?;
+
+        let mut bonuses = self.nodes[node_idx].output_bonuses(
+            &self.bonuses,
+            &self.building_grid,
+            &self.building_sites,
+        );
+        Ok(bonuses.remove(&building).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod bonus_tests {
+    use super::*;
+    use crate::game::game::colonized_game;
+
+    #[test]
+    fn builtin_bonuses() {
+        let bonuses = Bonuses::default();
+        assert_eq!(bonuses.scale[BuildingKind::Farm], 0.05);
+        assert_eq!(bonuses.scale[BuildingKind::Market], 0.);
+        assert_eq!(
+            bonuses.adjacency[BuildingKind::Farm][BuildingKind::Factory],
+            -0.1
+        );
+    }
+
+    #[test]
+    fn reject_invalid_bonuses() {
+        match Bonuses::parse("max_scale = 1.0\n[[scale]]\nbuilding = \"House\"\nbonus = 0.1\n") {
+            Err(BonusError::NotAProducer(BuildingKind::House)) => {}
+            result => panic!("unexpected {:?}", result),
+        }
+        let entry = "[[adjacency]]\nbuilding = \"Mine\"\nneighbour = \"Mine\"\nbonus = 0.1\n";
+        match Bonuses::parse(&format!("max_scale = 1.0\n{}{}", entry, entry)) {
+            Err(BonusError::DuplicateAdjacency { .. }) => {}
+            result => panic!("unexpected {:?}", result),
+        }
+        match Bonuses::parse("[[scale]]\nbuilding = \"Farm\"\nbonus = 0.1\n") {
+            Err(BonusError::Format(_)) => {}
+            result => panic!("unexpected {:?}", result),
+        }
+    }
+
+    #[test]
+    fn farms_gain_from_scale_and_neighbours() {
+        let (mut g, node_idx) = colonized_game(100);
+        let coor = g.nodes[node_idx].coor;
+        let [x, y] = [coor.x as u32, coor.y as u32];
+
+        let farm = g.create_farm([x + 3, y]).unwrap();
+        assert_eq!(g.output_bonus(farm).unwrap().multiplier(), 1.);
+
+        // a second farm two tiles away, and a warehouse right below the first one
+        g.create_farm([x + 3, y + 4]).unwrap();
+        let warehouse = g.create_warehouse([x + 3, y + 2]).unwrap();
+        let bonus = g.output_bonus(farm).unwrap();
+        assert_eq!(bonus.same_kind, 1);
+        assert_eq!(bonus.scale, 0.05);
+        assert_eq!(bonus.neighbours, vec![(warehouse, 0.1)]);
+        assert_eq!(
+            g.output_bonus(warehouse),
+            Err(GameError::NotAProducer(warehouse))
+        );
+
+        // both farms touch the warehouse: 2 * 20 * 1.15 grain
+        let node = &mut g.nodes[node_idx];
+        let bonuses = node.output_bonuses(&g.bonuses, &g.building_grid, &g.building_sites);
+        let crop = node.crop().unwrap();
+        let stock = node.market[crop];
+        node.produce(&g.entities.buildings, &bonuses, &mut Vec::new());
+        assert_eq!(node.market[crop], stock + 46);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::game::arena::{Arena, Handle};
+use crate::game::economy::FARM_OUTPUT;
+use crate::game::recipe::Workplace;
+use crate::game::BuildingId;
+use crate::game::Buildings;
+use crate::game::Farm;
+use crate::game::Household;
+use crate::game::Industry;
+use std::fmt;
+
+/// the kinds of BuildingId, without the index
+#[derive(Enum, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum BuildingKind {
+    Warehouse,
+    Market,
+    ColonyShipLanding,
+    AssemblyHall,
+    CorpHQ,
+    Farm,
+    Mine,
+    Workshop,
+    Factory,
+    House,
+    Skyscraper,
+}
+
+/// construction rules of a kind of building
+#[derive(Clone, Copy, Debug)]
+pub struct BuildingSpec {
+    pub name: &'static str,    // as typed in commands
+    pub footprint: (u32, u32), // width, height in tiles
+    pub cost: u32,             // credits, paid by the nation that owns the city
+    pub per_city_limit: Option<usize>,
+    pub demolishable: bool,
+}
+
+impl BuildingKind {
+    pub const ALL: [BuildingKind; 11] = [
+        BuildingKind::Warehouse,
+        BuildingKind::Market,
+        BuildingKind::ColonyShipLanding,
+        BuildingKind::AssemblyHall,
+        BuildingKind::CorpHQ,
+        BuildingKind::Farm,
+        BuildingKind::Mine,
+        BuildingKind::Workshop,
+        BuildingKind::Factory,
+        BuildingKind::House,
+        BuildingKind::Skyscraper,
+    ];
+
+    pub fn spec(self) -> BuildingSpec {
+        let (name, footprint, cost, per_city_limit) = match self {
+            BuildingKind::Warehouse => ("warehouse", (2, 2), 500, None),
+            BuildingKind::Market => ("market", (3, 3), 1000, Some(1)),
+            BuildingKind::ColonyShipLanding => ("colony-ship-landing", (3, 3), 0, Some(1)),
+            BuildingKind::AssemblyHall => ("assembly-hall", (3, 3), 2000, Some(1)),
+            BuildingKind::CorpHQ => ("corp-hq", (2, 2), 1500, None),
+            BuildingKind::Farm => ("farm", (2, 2), 200, None),
+            BuildingKind::Mine => ("mine", (2, 2), 400, None),
+            BuildingKind::Workshop => ("workshop", (2, 2), 300, None),
+            BuildingKind::Factory => ("factory", (3, 3), 800, None),
+            BuildingKind::House => ("house", (1, 1), 100, None),
+            BuildingKind::Skyscraper => ("skyscraper", (2, 2), 1200, None),
+        };
+
+        // the landing marks the node as colonized
+        let demolishable = self != BuildingKind::ColonyShipLanding;
+
+        BuildingSpec {
+            name,
+            footprint,
+            cost,
+            per_city_limit,
+            demolishable,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<BuildingKind> {
+        Self::ALL
+            .iter()
+            .cloned()
+            .find(|kind| kind.spec().name == name)
+    }
+
+    pub fn id(self, idx: Handle) -> BuildingId {
+        match self {
+            BuildingKind::Warehouse => BuildingId::Warehouse(idx),
+            BuildingKind::Market => BuildingId::Market(idx),
+            BuildingKind::ColonyShipLanding => BuildingId::ColonyShipLanding(idx),
+            BuildingKind::AssemblyHall => BuildingId::AssemblyHall(idx),
+            BuildingKind::CorpHQ => BuildingId::CorpHQ(idx),
+            BuildingKind::Farm => BuildingId::Farm(idx),
+            BuildingKind::Mine => BuildingId::Mine(idx),
+            BuildingKind::Workshop => BuildingId::Workshop(idx),
+            BuildingKind::Factory => BuildingId::Factory(idx),
+            BuildingKind::House => BuildingId::House(idx),
+            BuildingKind::Skyscraper => BuildingId::Skyscraper(idx),
+        }
+    }
+}
+
+impl Workplace {
+    pub fn building_kind(self) -> BuildingKind {
+        match self {
+            Workplace::Mine => BuildingKind::Mine,
+            Workplace::Workshop => BuildingKind::Workshop,
+            Workplace::Factory => BuildingKind::Factory,
+        }
+    }
+}
+
+impl BuildingId {
+    pub fn kind(self) -> BuildingKind {
+        self.split().0
+    }
+
+    pub fn handle(self) -> Handle {
+        self.split().1
+    }
+
+    fn split(self) -> (BuildingKind, Handle) {
+        match self {
+            BuildingId::Warehouse(idx) => (BuildingKind::Warehouse, idx),
+            BuildingId::Market(idx) => (BuildingKind::Market, idx),
+            BuildingId::ColonyShipLanding(idx) => (BuildingKind::ColonyShipLanding, idx),
+            BuildingId::AssemblyHall(idx) => (BuildingKind::AssemblyHall, idx),
+            BuildingId::CorpHQ(idx) => (BuildingKind::CorpHQ, idx),
+            BuildingId::Farm(idx) => (BuildingKind::Farm, idx),
+            BuildingId::Mine(idx) => (BuildingKind::Mine, idx),
+            BuildingId::Workshop(idx) => (BuildingKind::Workshop, idx),
+            BuildingId::Factory(idx) => (BuildingKind::Factory, idx),
+            BuildingId::House(idx) => (BuildingKind::House, idx),
+            BuildingId::Skyscraper(idx) => (BuildingKind::Skyscraper, idx),
+        }
+    }
+
+    /// parse the printed form of an id, e.g. Farm(0) or Farm(0v1)
+    pub fn from_name(name: &str) -> Option<BuildingId> {
+        let open = name.find('(')?;
+        let idx = name[open + 1..].strip_suffix(')')?.parse().ok()?;
+        let kind = BuildingKind::ALL
+            .iter()
+            .find(|kind| format!("{:?}", kind) == name[..open])?;
+        Some(kind.id(idx))
+    }
+}
+
+impl fmt::Display for BuildingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.spec().name)
+    }
+}
+
+impl Buildings {
+    /// store a new building of a kind; industries start without a recipe
+    pub(crate) fn insert(&mut self, kind: BuildingKind) -> BuildingId {
+        let handle = match kind {
+            BuildingKind::Warehouse => self.warehouses.insert(Default::default()),
+            BuildingKind::Market => self.markets.insert(Default::default()),
+            BuildingKind::ColonyShipLanding => self.landings.insert(Default::default()),
+            BuildingKind::AssemblyHall => self.assembly_halls.insert(Default::default()),
+            BuildingKind::CorpHQ => self.corp_hqs.insert(Default::default()),
+            BuildingKind::Farm => self.farms.insert(Farm {
+                output: FARM_OUTPUT,
+            }),
+            BuildingKind::Mine => self.mines.insert(Industry::default()),
+            BuildingKind::Workshop => self.workshops.insert(Industry::default()),
+            BuildingKind::Factory => self.factories.insert(Industry::default()),
+            BuildingKind::House => self.houses.insert(Household::default()),
+            BuildingKind::Skyscraper => self.skyscrapers.insert(Household::default()),
+        };
+        kind.id(handle)
+    }
+
+    /// false for demolished buildings, even if their slot has been reused
+    pub fn contains(&self, building: BuildingId) -> bool {
+        match building {
+            BuildingId::Warehouse(handle) => self.warehouses.contains(handle),
+            BuildingId::Market(handle) => self.markets.contains(handle),
+            BuildingId::ColonyShipLanding(handle) => self.landings.contains(handle),
+            BuildingId::AssemblyHall(handle) => self.assembly_halls.contains(handle),
+            BuildingId::CorpHQ(handle) => self.corp_hqs.contains(handle),
+            BuildingId::Farm(handle) => self.farms.contains(handle),
+            BuildingId::Mine(handle) => self.mines.contains(handle),
+            BuildingId::Workshop(handle) => self.workshops.contains(handle),
+            BuildingId::Factory(handle) => self.factories.contains(handle),
+            BuildingId::House(handle) => self.houses.contains(handle),
+            BuildingId::Skyscraper(handle) => self.skyscrapers.contains(handle),
+        }
+    }
+
+    /// returns false if the building was already gone
+    pub(crate) fn remove(&mut self, building: BuildingId) -> bool {
+        fn remove<T>(arena: &mut Arena<T>, handle: Handle) -> bool {
+            arena.remove(handle).is_some()
+        }
+
+        match building {
+            BuildingId::Warehouse(handle) => remove(&mut self.warehouses, handle),
+            BuildingId::Market(handle) => remove(&mut self.markets, handle),
+            BuildingId::ColonyShipLanding(handle) => remove(&mut self.landings, handle),
+            BuildingId::AssemblyHall(handle) => remove(&mut self.assembly_halls, handle),
+            BuildingId::CorpHQ(handle) => remove(&mut self.corp_hqs, handle),
+            BuildingId::Farm(handle) => remove(&mut self.farms, handle),
+            BuildingId::Mine(handle) => remove(&mut self.mines, handle),
+            BuildingId::Workshop(handle) => remove(&mut self.workshops, handle),
+            BuildingId::Factory(handle) => remove(&mut self.factories, handle),
+            BuildingId::House(handle) => remove(&mut self.houses, handle),
+            BuildingId::Skyscraper(handle) => remove(&mut self.skyscrapers, handle),
+        }
+    }
+
+    pub(crate) fn industry(&self, building: BuildingId) -> Option<&Industry> {
+        match building {
+            BuildingId::Mine(handle) => self.mines.get(handle),
+            BuildingId::Workshop(handle) => self.workshops.get(handle),
+            BuildingId::Factory(handle) => self.factories.get(handle),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn industry_mut(&mut self, building: BuildingId) -> Option<&mut Industry> {
+        match building {
+            BuildingId::Mine(handle) => self.mines.get_mut(handle),
+            BuildingId::Workshop(handle) => self.workshops.get_mut(handle),
+            BuildingId::Factory(handle) => self.factories.get_mut(handle),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod building_tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for &kind in BuildingKind::ALL.iter() {
+            assert_eq!(BuildingKind::from_name(kind.spec().name), Some(kind));
+
+            for &generation in &[0, 2] {
+                let id = kind.id(Handle::new(3, generation));
+                assert_eq!(id.kind(), kind);
+                assert_eq!(BuildingId::from_name(&format!("{:?}", id)), Some(id));
+            }
+        }
+        assert_eq!(BuildingId::from_name("Farm(x)"), None);
+        assert_eq!(BuildingId::from_name("Barn(1)"), None);
+    }
+}
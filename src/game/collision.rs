@@ -0,0 +1,186 @@
+use crate::algorithm::insertion_sort::insertion_sort;
+use crate::game::CharacterMobility;
+use crate::game::Game;
+use crate::geometry::rectangle::Rectangle;
+use crate::geometry::rectangle_rectangle;
+use nalgebra::Vector2;
+use std::collections::{BTreeMap, BTreeSet};
+
+/*
+Units collide when their bodies overlap, after they moved:
+- broadphase: sweep and prune over the units sorted by their center along each axis (unit_x_axis
+  and unit_y_axis); a pair is a candidate if the bounding boxes of its bodies overlap on both axes
+- narrowphase: the separating axis test between the two rectangles (see rectangle_rectangle.rs)
+- resolution: every pair is pushed back apart, each unit by half the penetration, so that bodies
+  never stay inside each other for more than a tick
+*/
+
+/// two overlapping units, and the shortest translation that moves the second out of the first
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collision {
+    pub units: (usize, usize), // character ids, lower first
+    pub penetration: Vector2<f32>,
+}
+
+/// bounding box of a body: min and max along x, then along y
+fn bounds(body: &Rectangle) -> [(f32, f32); 2] {
+    let mut bounds = [(f32::INFINITY, f32::NEG_INFINITY); 2];
+    for vertex in body.get_transformed_vertices().iter() {
+        for (axis, bound) in bounds.iter_mut().enumerate() {
+            bound.0 = bound.0.min(vertex[axis]);
+            bound.1 = bound.1.max(vertex[axis]);
+        }
+    }
+    bounds
+}
+
+/// pairs of units whose intervals overlap, from the units sorted by the center of their interval
+fn sweep_and_prune<F: Fn(usize) -> (f32, f32)>(
+    sorted: &[usize],
+    interval: F,
+    max_half_extent: f32,
+) -> BTreeSet<(usize, usize)> {
+    let mut pairs = BTreeSet::new();
+    for (idx, &a) in sorted.iter().enumerate() {
+        let (a_min, a_max) = interval(a);
+        for &b in &sorted[idx + 1..] {
+            let (b_min, b_max) = interval(b);
+            // the following units are centered even further, past the end of a
+            if (b_min + b_max) / 2. - max_half_extent > a_max {
+                break;
+            }
+            if b_min < a_max && a_min < b_max {
+                pairs.insert((a.min(b), a.max(b)));
+            }
+        }
+    }
+    pairs
+}
+
+impl Game {
+    fn unit_body(&self, character_idx: usize) -> Option<&Rectangle> {
+        match &self.entities.characters[character_idx].mobility {
+            CharacterMobility::Unit { body, .. } => Some(body),
+            CharacterMobility::Parked { .. } => None,
+        }
+    }
+
+    /// units whose bodies overlap, in order
+    pub fn collisions(&self) -> Vec<Collision> {
+        let bounds: BTreeMap<usize, [(f32, f32); 2]> = self
+            .unit_x_axis
+            .iter()
+            .filter_map(|&idx| self.unit_body(idx).map(|body| (idx, bounds(body))))
+            .collect();
+        let max_half_extent = bounds
+            .values()
+            .flat_map(|bounds| bounds.iter().map(|(min, max)| (max - min) / 2.))
+            .fold(0., f32::max);
+
+        // units that were deployed or parked since the last update are out of place
+        let mut candidates = None;
+        for (axis, sorted) in [&self.unit_x_axis, &self.unit_y_axis].iter().enumerate() {
+            let mut sorted: Vec<_> = sorted
+                .iter()
+                .cloned()
+                .filter(|idx| bounds.contains_key(idx))
+                .collect();
+            insertion_sort(&mut sorted, |a, b| {
+                let center = |idx: &usize| bounds[idx][axis].0 + bounds[idx][axis].1;
+                center(a).partial_cmp(&center(b)).unwrap()
+            });
+
+            let pairs = sweep_and_prune(&sorted, |idx| bounds[&idx][axis], max_half_extent);
+            candidates = Some(match candidates {
+                None => pairs,
+                Some(candidates) => &candidates & &pairs,
+            });
+        }
+
+        candidates
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let a_body = self.unit_body(a)?;
+                let b_body = self.unit_body(b)?;
+                rectangle_rectangle::penetration(a_body, b_body).map(|penetration| Collision {
+                    units: (a, b),
+                    penetration,
+                })
+            })
+            .collect()
+    }
+
+    /// push overlapping units apart, then sort the axes again
+    pub(crate) fn resolve_collisions(&mut self) {
+        let mut pushes: BTreeMap<usize, Vector2<f32>> = BTreeMap::new();
+        for collision in self.collisions() {
+            let (a, b) = collision.units;
+            let half = collision.penetration / 2.;
+            *pushes.entry(a).or_insert_with(Vector2::zeros) -= half;
+            *pushes.entry(b).or_insert_with(Vector2::zeros) += half;
+        }
+        if pushes.is_empty() {
+            return;
+        }
+
+        let entities = &mut self.entities;
+        for (character_idx, push) in pushes {
+            if let CharacterMobility::Unit { body, .. } =
+                &mut entities.characters[character_idx].mobility
+            {
+                body.center += push;
+            }
+        }
+        insertion_sort(&mut self.unit_x_axis, |a, b| {
+            entities.unit_comparator_by_x(*a, *b)
+        });
+        insertion_sort(&mut self.unit_y_axis, |a, b| {
+            entities.unit_comparator_by_y(*a, *b)
+        });
+    }
+}
+
+#[cfg(test)]
+mod collision_tests {
+    use super::*;
+    use crate::game::game::colonized_game;
+
+    #[test]
+    fn sweep_prunes_far_intervals() {
+        let intervals = [(0., 2.), (1., 3.), (2.5, 3.5), (10., 12.)];
+        let pairs = sweep_and_prune(&[0, 1, 2, 3], |idx| intervals[idx], 1.);
+        assert_eq!(pairs.into_iter().collect::<Vec<_>>(), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn units_deployed_together_are_pushed_apart() {
+        let (mut g, _) = colonized_game(100);
+        g.deploy_character(0).unwrap();
+        g.deploy_character(1).unwrap();
+
+        let collisions = g.collisions();
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].units, (0, 1));
+        assert!((collisions[0].penetration.norm() - 1.).abs() < 0.0001);
+
+        g.update_state();
+        assert!(g.collisions().is_empty());
+        let a = g.get_unit_coor(0).unwrap();
+        let b = g.get_unit_coor(1).unwrap();
+        assert!((nalgebra::distance(&a, &b) - 1.).abs() < 0.0001);
+    }
+
+    #[test]
+    fn far_units_dont_collide() {
+        let (mut g, _) = colonized_game(100);
+        g.deploy_character(0).unwrap();
+        g.deploy_character(1).unwrap();
+        let coor = g.get_unit_coor(1).unwrap();
+        if let CharacterMobility::Unit { body, .. } = &mut g.entities.characters[1].mobility {
+            body.center = coor + Vector2::new(1., 1.);
+        }
+        // the bounding boxes touch, the bodies don't overlap
+        assert!(g.collisions().is_empty());
+    }
+}
@@ -0,0 +1,467 @@
+use crate::game::building::BuildingKind;
+use crate::game::money::{Account, Money, TransactionKind};
+use crate::game::BuildingId;
+use crate::game::CharacterMobility;
+use crate::game::Corporation;
+use crate::game::Game;
+use crate::game::GameError;
+use std::collections::{HashMap, HashSet};
+
+/*
+A character founds a corporation by building their first business in the city they're parked
+in: they invest the cost of the building, and get every share of the new corporation. From then
+on, the corporation pays for the businesses that its CEO builds, and for its one headquarters.
+
+The CEO is the largest shareholder; on a tie, the current CEO stays. Businesses make money by
+selling to their node's market: warehouses trade, while farms, mines, workshops and factories sell
+everything they produce. Every few ticks, the businesses hand what they made (or lost) over to
+their corporation, which pays part of its cash to the shareholders.
+*/
+
+/// shares issued to the founder of a corporation
+pub const FOUNDING_SHARES: u32 = 100;
+
+/// credits granted by the World to every starting colonist
+pub const STARTING_SAVINGS: f32 = 1000.;
+
+/// ticks between two dividend payouts
+pub const DIVIDEND_PERIOD: u64 = 10;
+
+/// percentage of a corporation's cash paid out as dividends
+pub const DIVIDEND_PAYOUT: u32 = 50;
+
+impl BuildingKind {
+    /// production and commerce buildings, which characters run through corporations
+    pub fn is_business(self) -> bool {
+        matches!(
+            self,
+            BuildingKind::Warehouse
+                | BuildingKind::Farm
+                | BuildingKind::Mine
+                | BuildingKind::Workshop
+                | BuildingKind::Factory
+        )
+    }
+}
+
+impl Corporation {
+    fn new(founder: usize) -> Self {
+        let mut shareholders = HashMap::new();
+        shareholders.insert(founder, FOUNDING_SHARES);
+        Self {
+            influence: 0,
+            buildings: HashSet::new(),
+            hq: None,
+            ceo: founder,
+            num_issued_shares: FOUNDING_SHARES,
+            shareholders,
+        }
+    }
+
+    pub fn influence(&self) -> u32 {
+        self.influence
+    }
+
+    pub fn ceo(&self) -> usize {
+        self.ceo
+    }
+
+    pub fn hq(&self) -> Option<BuildingId> {
+        self.hq
+    }
+
+    pub fn num_issued_shares(&self) -> u32 {
+        self.num_issued_shares
+    }
+
+    pub fn shares_of(&self, character_idx: usize) -> u32 {
+        self.shareholders.get(&character_idx).cloned().unwrap_or(0)
+    }
+
+    /// character id and number of shares, by character id
+    pub fn shareholders(&self) -> Vec<(usize, u32)> {
+        let mut shareholders: Vec<_> = self
+            .shareholders
+            .iter()
+            .map(|(&character_idx, &shares)| (character_idx, shares))
+            .collect();
+        shareholders.sort_unstable();
+        shareholders
+    }
+
+    /// businesses and headquarters, in id order
+    pub fn buildings(&self) -> Vec<BuildingId> {
+        let mut buildings: Vec<_> = self.buildings.iter().cloned().collect();
+        buildings.sort_unstable();
+        buildings
+    }
+
+    pub(crate) fn remove_building(&mut self, building_id: BuildingId) {
+        self.buildings.remove(&building_id);
+        if self.hq == Some(building_id) {
+            self.hq = None;
+        }
+    }
+
+    fn elect_ceo(&mut self) {
+        let ceo_shares = self.shares_of(self.ceo);
+        if let Some((character_idx, _)) = self
+            .shareholders()
+            .into_iter()
+            .filter(|&(_, shares)| shares > ceo_shares)
+            .max_by_key(|&(character_idx, shares)| (shares, std::cmp::Reverse(character_idx)))
+        {
+            self.ceo = character_idx;
+        }
+    }
+}
+
+impl Game {
+    pub fn corporation(&self, corporation_idx: usize) -> Result<&Corporation, GameError> {
+        self.corporations
+            .get(corporation_idx)
+            .ok_or(GameError::InvalidCorporationIndex(corporation_idx))
+    }
+
+    /// the first corporation led by the character
+    pub fn corporation_of_ceo(&self, character_idx: usize) -> Option<usize> {
+        self.corporations
+            .iter()
+            .position(|corporation| corporation.ceo == character_idx)
+    }
+
+    pub fn corporation_of_building(&self, building_id: BuildingId) -> Option<usize> {
+        self.corporations
+            .iter()
+            .position(|corporation| corporation.buildings.contains(&building_id))
+    }
+
+    /// build a business or a headquarters for the corporation led by the character; a character
+    /// without one founds it with their first business
+    pub fn build_business(
+        &mut self,
+        character_idx: usize,
+        kind: BuildingKind,
+        top_left: [u32; 2],
+    ) -> Result<BuildingId, GameError> {
+        self.check_character_idx(character_idx)?;
+        if !kind.is_business() && kind != BuildingKind::CorpHQ {
+            return Err(GameError::NotABusiness(kind));
+        }
+
+        let node_idx = self.check_construction(kind, top_left)?;
+        match self.entities.characters[character_idx].mobility {
+            CharacterMobility::Parked { node_idx: parked } if parked == node_idx => {}
+            CharacterMobility::Parked { .. } => {
+                return Err(GameError::CharacterNotInNode {
+                    character_idx,
+                    node_idx,
+                })
+            }
+            CharacterMobility::Unit { .. } => {
+                return Err(GameError::CharacterNotParked(character_idx))
+            }
+        }
+
+        let corporation_idx = match self.corporation_of_ceo(character_idx) {
+            Some(corporation_idx) => corporation_idx,
+            None if kind == BuildingKind::CorpHQ => {
+                return Err(GameError::NotACeo(character_idx));
+            }
+            None => {
+                let founder = Account::Character(character_idx);
+                let cost = Money::from_credits(kind.spec().cost as f32);
+                let funds = self.ledger.balance(founder);
+                if funds < cost {
+                    return Err(GameError::InsufficientFunds {
+                        account: founder,
+                        cost,
+                        funds,
+                    });
+                }
+
+                self.corporations.push(Corporation::new(character_idx));
+                let corporation_idx = self.corporations.len() - 1;
+                self.ledger.transfer(
+                    self.tick,
+                    TransactionKind::Investment,
+                    founder,
+                    Account::Corporation(corporation_idx),
+                    cost,
+                );
+                corporation_idx
+            }
+        };
+
+        if kind == BuildingKind::CorpHQ {
+            if let Some(hq) = self.corporations[corporation_idx].hq {
+                return Err(GameError::HQAlreadyBuilt {
+                    corporation_idx,
+                    hq,
+                });
+            }
+        }
+
+        let payer = Account::Corporation(corporation_idx);
+        let building_id = self.pay_and_insert_building(kind, top_left, node_idx, payer)?;
+
+        let corporation = &mut self.corporations[corporation_idx];
+        corporation.buildings.insert(building_id);
+        if kind == BuildingKind::CorpHQ {
+            corporation.hq = Some(building_id);
+        }
+        Ok(building_id)
+    }
+
+    /// new shares go to the CEO
+    pub fn issue_shares(&mut self, corporation_idx: usize, qty: u32) -> Result<(), GameError> {
+        self.corporation(corporation_idx)?;
+
+        let corporation = &mut self.corporations[corporation_idx];
+        let too_many = GameError::TooManyShares {
+            corporation_idx,
+            issued: corporation.num_issued_shares,
+        };
+        let issued = corporation
+            .num_issued_shares
+            .checked_add(qty)
+            .ok_or(too_many.clone())?;
+        let ceo_shares = corporation
+            .shares_of(corporation.ceo)
+            .checked_add(qty)
+            .ok_or(too_many)?;
+
+        corporation.num_issued_shares = issued;
+        corporation.shareholders.insert(corporation.ceo, ceo_shares);
+        Ok(())
+    }
+
+    /// give shares to another character; the largest shareholder becomes the CEO
+    pub fn transfer_shares(
+        &mut self,
+        corporation_idx: usize,
+        from: usize,
+        to: usize,
+        qty: u32,
+    ) -> Result<(), GameError> {
+        self.check_character_idx(from)?;
+        self.check_character_idx(to)?;
+        let owned = self.corporation(corporation_idx)?.shares_of(from);
+        if owned < qty {
+            return Err(GameError::NotEnoughShares {
+                corporation_idx,
+                character_idx: from,
+                owned,
+            });
+        }
+
+        let corporation = &mut self.corporations[corporation_idx];
+        if owned == qty {
+            corporation.shareholders.remove(&from);
+        } else {
+            corporation.shareholders.insert(from, owned - qty);
+        }
+        *corporation.shareholders.entry(to).or_insert(0) += qty;
+        corporation.elect_ceo();
+        Ok(())
+    }
+
+    /// businesses settle with their corporation, which then pays dividends out of its cash
+    pub(crate) fn pay_dividends(&mut self) {
+        for (corporation_idx, corporation) in self.corporations.iter().enumerate() {
+            let account = Account::Corporation(corporation_idx);
+
+            for building in corporation.buildings() {
+                let building = Account::Building(building);
+                let profit = self.ledger.balance(building);
+                let kind = TransactionKind::Profit;
+                if profit > Money::ZERO {
+                    self.ledger
+                        .transfer(self.tick, kind, building, account, profit);
+                } else {
+                    self.ledger
+                        .transfer(self.tick, kind, account, building, -profit);
+                }
+            }
+
+            let cash = self.ledger.balance(account);
+            if cash <= Money::ZERO {
+                continue;
+            }
+            let payout = cash.share(DIVIDEND_PAYOUT, 100);
+            for (character_idx, shares) in corporation.shareholders() {
+                let dividend = payout.share(shares, corporation.num_issued_shares);
+                self.ledger.transfer(
+                    self.tick,
+                    TransactionKind::Dividend,
+                    account,
+                    Account::Character(character_idx),
+                    dividend,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod corporation_tests {
+    use super::*;
+    use crate::game::game::colonized_game;
+
+    fn game_with_colony() -> (Game, usize, [u32; 2]) {
+        let (g, node_idx) = colonized_game(100);
+        let coor = g.nodes[node_idx].coor;
+        (g, node_idx, [coor.x as u32, coor.y as u32])
+    }
+
+    #[test]
+    fn found_a_corporation_with_the_first_business() {
+        let (mut g, _, [x, y]) = game_with_colony();
+
+        assert_eq!(
+            g.build_business(0, BuildingKind::CorpHQ, [x - 2, y + 2]),
+            Err(GameError::NotACeo(0))
+        );
+
+        let warehouse = g
+            .build_business(0, BuildingKind::Warehouse, [x - 2, y + 2])
+            .unwrap();
+        let corporation = g.corporation(0).unwrap();
+        assert_eq!(corporation.ceo(), 0);
+        assert_eq!(corporation.shareholders(), vec![(0, FOUNDING_SHARES)]);
+        assert_eq!(corporation.buildings(), vec![warehouse]);
+        // the founder's investment paid for the warehouse
+        assert_eq!(
+            g.ledger.balance(Account::Character(0)),
+            Money::from_credits(STARTING_SAVINGS - 500.)
+        );
+        assert_eq!(g.ledger.balance(Account::Corporation(0)), Money::ZERO);
+
+        assert_eq!(
+            g.build_business(0, BuildingKind::House, [x, y + 2]),
+            Err(GameError::NotABusiness(BuildingKind::House))
+        );
+        // the corporation can't afford its headquarters yet
+        match g.build_business(0, BuildingKind::CorpHQ, [x, y + 2]) {
+            Err(GameError::InsufficientFunds { account, .. }) => {
+                assert_eq!(account, Account::Corporation(0))
+            }
+            result => panic!("unexpected {:?}", result),
+        }
+        g.ledger.transfer(
+            0,
+            TransactionKind::Grant,
+            Account::World,
+            Account::Corporation(0),
+            Money::from_credits(3000.),
+        );
+        let hq = g
+            .build_business(0, BuildingKind::CorpHQ, [x, y + 2])
+            .unwrap();
+        assert_eq!(
+            g.build_business(0, BuildingKind::CorpHQ, [x + 2, y + 2]),
+            Err(GameError::HQAlreadyBuilt {
+                corporation_idx: 0,
+                hq
+            })
+        );
+
+        g.demolish(hq).unwrap();
+        assert_eq!(g.corporation(0).unwrap().hq(), None);
+    }
+
+    #[test]
+    fn the_largest_shareholder_leads() {
+        let (mut g, _, [x, y]) = game_with_colony();
+        g.build_business(0, BuildingKind::Farm, [x - 2, y + 2])
+            .unwrap();
+
+        g.transfer_shares(0, 0, 1, 50).unwrap();
+        assert_eq!(g.corporation(0).unwrap().ceo(), 0);
+        g.transfer_shares(0, 0, 2, 10).unwrap();
+        assert_eq!(g.corporation(0).unwrap().ceo(), 1);
+        assert_eq!(
+            g.transfer_shares(0, 0, 2, 41),
+            Err(GameError::NotEnoughShares {
+                corporation_idx: 0,
+                character_idx: 0,
+                owned: 40
+            })
+        );
+
+        g.issue_shares(0, 20).unwrap();
+        let corporation = g.corporation(0).unwrap();
+        assert_eq!(corporation.num_issued_shares(), 120);
+        assert_eq!(corporation.shareholders(), vec![(0, 40), (1, 70), (2, 10)]);
+
+        // a failed issue leaves the shares as they were
+        assert_eq!(
+            g.issue_shares(0, u32::MAX),
+            Err(GameError::TooManyShares {
+                corporation_idx: 0,
+                issued: 120
+            })
+        );
+        let corporation = g.corporation(0).unwrap();
+        assert_eq!(corporation.num_issued_shares(), 120);
+        assert_eq!(corporation.shares_of(1), 70);
+    }
+
+    #[test]
+    fn profits_pay_dividends() {
+        let (mut g, _, [x, y]) = game_with_colony();
+        let warehouse = g
+            .build_business(0, BuildingKind::Warehouse, [x - 2, y + 2])
+            .unwrap();
+        g.transfer_shares(0, 0, 1, 25).unwrap();
+
+        let account = Account::Building(warehouse);
+        let sale = Money::from_credits(1000.);
+        g.ledger
+            .transfer(0, TransactionKind::Sale, Account::World, account, sale);
+        for _ in 0..DIVIDEND_PERIOD {
+            g.update_state();
+        }
+
+        // 1000 of sales minus 10 of upkeep, half of which is paid out
+        assert_eq!(g.ledger.balance(account), Money::ZERO);
+        assert_eq!(
+            g.ledger.balance(Account::Corporation(0)),
+            Money::from_credits(495.)
+        );
+        let savings = Money::from_credits(STARTING_SAVINGS);
+        assert_eq!(
+            g.ledger.balance(Account::Character(0)),
+            savings - Money::from_credits(500. - 371.25)
+        );
+        assert_eq!(
+            g.ledger.balance(Account::Character(1)),
+            savings + Money::from_credits(123.75)
+        );
+        assert_eq!(g.ledger.audit(), Ok(()));
+    }
+
+    #[test]
+    fn harvests_pay_dividends() {
+        let (mut g, node_idx, [x, y]) = game_with_colony();
+        let farm = g
+            .build_business(0, BuildingKind::Farm, [x - 2, y + 2])
+            .unwrap();
+        g.transfer_shares(0, 0, 1, 25).unwrap();
+        for _ in 0..DIVIDEND_PERIOD {
+            g.update_state();
+        }
+
+        // the city bought every harvest, and the farm handed the sales over to its corporation,
+        // which paid half of them out
+        let sales = -g.ledger.balance(Account::City(node_idx));
+        assert!(sales > Money::ZERO);
+        assert_eq!(g.ledger.balance(Account::Building(farm)), Money::ZERO);
+        let payout = sales.share(DIVIDEND_PAYOUT, 100);
+        assert_eq!(
+            g.ledger.balance(Account::Character(1)),
+            Money::from_credits(STARTING_SAVINGS) + payout.share(25, 100)
+        );
+        assert_eq!(g.ledger.audit(), Ok(()));
+    }
+}
@@ -0,0 +1,336 @@
+use crate::game::bonus::OutputBonus;
+use crate::game::recipe::{Recipe, Recipes};
+use crate::game::BuildingId;
+use crate::game::Buildings;
+use crate::game::Commodity;
+use crate::game::Node;
+use enum_map::EnumMap;
+use std::collections::BTreeMap;
+
+/// crops harvested by a farm every tick
+pub const FARM_OUTPUT: u32 = 20;
+
+/// every tick, each group of this many people (rounded up) eats one unit of food
+pub const PEOPLE_PER_FOOD: usize = 10;
+
+/// a node's price is the base price when its market holds this much of the commodity, plus one
+/// tick of demand
+pub const REFERENCE_STOCK: u32 = 50;
+
+/// credits earned by a worker for a tick of work in an industry
+pub const WAGE: f32 = 0.1;
+
+/// number of closing prices remembered by a node
+pub const PRICE_HISTORY_LENGTH: usize = 20;
+
+impl Commodity {
+    /// every commodity, in the order of declaration
+    pub fn all() -> impl Iterator<Item = Commodity> {
+        EnumMap::<Commodity, ()>::default()
+            .into_iter()
+            .map(|(commodity, _)| commodity)
+    }
+
+    /// grown on farms
+    pub fn is_agricultural(self) -> bool {
+        use self::Commodity::*;
+        matches!(
+            self,
+            Cotton | Hemp | Sugar | Cocoa | Grain | Fruit | Coffee | Spice | Tea
+        )
+    }
+
+    /// price at a node holding the reference stock
+    pub fn base_price(self) -> f32 {
+        use self::Commodity::*;
+        match self {
+            Grain | Fruit => 5.,
+            Sand | Wood | Cotton | Hemp | Sugar | Cocoa | Chemical | Rubber => 10.,
+            Coffee | Spice | Tea => 15.,
+            Steel | Aluminum | Oil | Uranium | Deuterium => 20.,
+            Fat | Meat | Milk | Egg => 12.,
+            Silicon | Glass | Plastic => 30.,
+            Diamond | Gold => 80.,
+            Candy | Cake | Chocolate | Wine | Apparel | Furniture | Medicine => 50.,
+            Computer | Jewelry | Gadget | Vehicle => 150.,
+        }
+    }
+
+    /// eaten by civilians, in the order of declaration
+    pub fn is_food(self) -> bool {
+        use self::Commodity::*;
+        matches!(
+            self,
+            Grain | Fruit | Meat | Milk | Egg | Cake | Candy | Chocolate
+        )
+    }
+}
+
+impl Node {
+    /// the crop of the node's farms; None if nothing grows here
+    pub fn crop(&self) -> Option<Commodity> {
+        self.resources
+            .iter()
+            .cloned()
+            .find(|commodity| commodity.is_agricultural())
+    }
+
+    /// food eaten by the population in one tick
+    pub fn food_demand(&self) -> u32 {
+        self.population.div_ceil(PEOPLE_PER_FOOD) as u32
+    }
+
+    /// twice the base price when nothing is in stock, falling towards 0 as the stock outgrows
+    /// the demand; the price moves as soon as the stock does, so trades within a tick feel it
+    pub fn price(&self, commodity: Commodity) -> f32 {
+        let stock = self.market[commodity] as f32;
+        let reference = (REFERENCE_STOCK + self.demand[commodity]) as f32;
+        commodity.base_price() * 2. * reference / (stock + reference)
+    }
+
+    /// take units out of the market, counting them towards the demand
+    pub(crate) fn take(&mut self, commodity: Commodity, qty: u32) {
+        self.market[commodity] -= qty;
+        self.consumed[commodity] += qty;
+    }
+
+    /// end of tick: this tick's consumption becomes the demand, and the prices are recorded
+    pub(crate) fn close_market(&mut self) {
+        self.demand = self.consumed;
+        self.consumed = Default::default();
+
+        if self.price_history.len() == PRICE_HISTORY_LENGTH {
+            self.price_history.pop_front();
+        }
+        let mut prices = EnumMap::default();
+        for (commodity, price) in prices.iter_mut() {
+            *price = self.price(commodity);
+        }
+        self.price_history.push_back(prices);
+    }
+
+    /// total amount of food in the market
+    pub fn food_supply(&self) -> u32 {
+        self.market
+            .iter()
+            .filter(|(commodity, _)| commodity.is_food())
+            .map(|(_, &qty)| qty)
+            .sum()
+    }
+
+    /// farms put their harvest into the market; each harvest is added to `produced`
+    pub fn produce(
+        &mut self,
+        buildings: &Buildings,
+        bonuses: &BTreeMap<BuildingId, OutputBonus>,
+        produced: &mut Vec<(BuildingId, Commodity, u32)>,
+    ) {
+        let crop = match self.crop() {
+            Some(crop) => crop,
+            None => return,
+        };
+
+        for building in &self.buildings {
+            if let BuildingId::Farm(handle) = building {
+                if let Some(farm) = buildings.farms.get(*handle) {
+                    let multiplier = bonuses.get(building).map_or(1., OutputBonus::multiplier);
+                    let harvest = (farm.output as f32 * multiplier).round() as u32;
+                    self.market[crop] += harvest;
+                    produced.push((*building, crop, harvest));
+                }
+            }
+        }
+    }
+
+    /// mines, workshops and factories run their recipe once, as long as workers and inputs last;
+    /// bonuses add up to extra outputs, without extra inputs; each output is added to `produced`;
+    /// returns the number of workers employed
+    pub fn run_industries(
+        &mut self,
+        buildings: &mut Buildings,
+        recipes: &Recipes,
+        bonuses: &BTreeMap<BuildingId, OutputBonus>,
+        produced: &mut Vec<(BuildingId, Commodity, u32)>,
+    ) -> usize {
+        let mut industries: Vec<_> = self
+            .buildings
+            .iter()
+            .cloned()
+            .filter(|&building| buildings.industry(building).is_some())
+            .collect();
+        // served in id order rather than in the hash set's order
+        industries.sort_unstable();
+
+        let mut idle_workers = self.population;
+
+        for building in industries {
+            let industry = buildings.industry_mut(building).unwrap();
+            let recipe = match industry.recipe.as_ref().and_then(|name| recipes.get(name)) {
+                Some(recipe) => recipe,
+                None => continue, // idle
+            };
+
+            if (recipe.labor as usize) <= idle_workers && self.has_inputs(recipe) {
+                idle_workers -= recipe.labor as usize;
+
+                // the inputs are only used once a run is done
+                let multiplier = bonuses.get(&building).map_or(1., OutputBonus::multiplier);
+                industry.progress += multiplier;
+                let runs = industry.progress.floor();
+                if runs < 1. {
+                    continue;
+                }
+                industry.progress -= runs;
+                for &(commodity, qty) in &recipe.inputs {
+                    self.take(commodity, qty);
+                }
+                for &(commodity, qty) in &recipe.outputs {
+                    self.market[commodity] += qty * runs as u32;
+                    produced.push((building, commodity, qty * runs as u32));
+                }
+            }
+        }
+
+        self.population - idle_workers
+    }
+
+    fn has_inputs(&self, recipe: &Recipe) -> bool {
+        recipe
+            .inputs
+            .iter()
+            .all(|&(commodity, qty)| self.market[commodity] >= qty)
+    }
+
+    /// civilians eat from the market; returns the demand that couldn't be met
+    pub fn consume_food(&mut self) -> u32 {
+        let mut demand = self.food_demand();
+
+        for commodity in Commodity::all().filter(|commodity| commodity.is_food()) {
+            if demand == 0 {
+                break;
+            }
+            let eaten = demand.min(self.market[commodity]);
+            self.take(commodity, eaten);
+            demand -= eaten;
+        }
+
+        demand
+    }
+}
+
+#[cfg(test)]
+mod economy_tests {
+    use crate::game::bonus::OutputBonus;
+    use crate::game::game::colonized_game;
+    use crate::game::Commodity;
+    use crate::GameOptions;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn farms_feed_the_node() {
+        let (mut g, node_idx) = colonized_game(100);
+        let coor = g.nodes[node_idx].coor;
+        let crop = g.nodes[node_idx].crop().unwrap();
+        g.create_farm([coor.x as u32 + 3, coor.y as u32]).unwrap();
+
+        g.update_state();
+        let node = &g.nodes[node_idx];
+        assert_eq!(node.market[crop], super::FARM_OUTPUT - node.food_demand());
+
+        // nodes without farms have nothing to eat
+        assert!((0..g.nodes.len())
+            .filter(|&idx| idx != node_idx)
+            .all(|idx| g.nodes[idx].food_supply() == 0));
+    }
+
+    #[test]
+    fn civilians_eat_every_kind_of_food() {
+        let mut g = GameOptions::default()
+            .set_seed(1234)
+            .set_starting_population(95)
+            .build()
+            .unwrap();
+        let node = &mut g.nodes[0];
+        node.market[Commodity::Grain] = 3;
+        node.market[Commodity::Fruit] = 4;
+        node.market[Commodity::Steel] = 5;
+
+        assert_eq!(node.food_demand(), 10);
+        assert_eq!(node.consume_food(), 3);
+        assert_eq!(node.food_supply(), 0);
+        assert_eq!(node.market[Commodity::Steel], 5);
+    }
+
+    #[test]
+    fn industries_share_workers() {
+        let (mut g, node_idx) = colonized_game(10);
+        let coor = g.nodes[node_idx].coor;
+        let [x, y] = [coor.x as u32, coor.y as u32];
+        for &tile in &[[x - 2, y + 2], [x, y + 2], [x + 2, y + 2]] {
+            g.create_industry(tile, "glass").unwrap();
+        }
+        g.nodes[node_idx].market[Commodity::Sand] = 10;
+
+        // 5 workers per run, so only 2 of the 3 workshops can work
+        g.update_state();
+        assert_eq!(g.nodes[node_idx].market[Commodity::Glass], 2);
+        assert_eq!(g.nodes[node_idx].market[Commodity::Sand], 6);
+    }
+
+    #[test]
+    fn slow_industries_keep_their_inputs_until_a_run_is_done() {
+        let (mut g, node_idx) = colonized_game(10);
+        let coor = g.nodes[node_idx].coor;
+        let workshop = g
+            .create_industry([coor.x as u32 - 2, coor.y as u32 + 2], "glass")
+            .unwrap();
+        let mut bonuses = BTreeMap::new();
+        let bonus = OutputBonus {
+            scale: -0.5,
+            ..Default::default()
+        };
+        bonuses.insert(workshop, bonus);
+
+        // half a run per tick
+        let node = &mut g.nodes[node_idx];
+        node.market[Commodity::Sand] = 10;
+        let buildings = &mut g.entities.buildings;
+        node.run_industries(buildings, &g.recipes, &bonuses, &mut Vec::new());
+        assert_eq!(node.market[Commodity::Sand], 10);
+        assert_eq!(node.market[Commodity::Glass], 0);
+        node.run_industries(buildings, &g.recipes, &bonuses, &mut Vec::new());
+        assert_eq!(node.market[Commodity::Sand], 8);
+        assert_eq!(node.market[Commodity::Glass], 1);
+    }
+
+    #[test]
+    fn demand_raises_prices() {
+        let mut g = GameOptions::default()
+            .set_seed(1234)
+            .set_starting_population(100)
+            .build()
+            .unwrap();
+        for node in &mut g.nodes[..2] {
+            node.market[Commodity::Fruit] = 50;
+        }
+        assert_eq!(g.nodes[0].price(Commodity::Fruit), 5.);
+
+        // only node 0 eats
+        g.nodes[1].population = 0;
+        g.update_state();
+
+        let (hungry, idle) = (&g.nodes[0], &g.nodes[1]);
+        assert_eq!(hungry.demand[Commodity::Fruit], 10);
+        assert!(hungry.price(Commodity::Fruit) > idle.price(Commodity::Fruit));
+        assert_eq!(idle.price(Commodity::Fruit), 5.);
+        assert_eq!(
+            hungry.price_history.back().unwrap()[Commodity::Fruit],
+            hungry.price(Commodity::Fruit)
+        );
+
+        for _ in 0..super::PRICE_HISTORY_LENGTH {
+            g.update_state();
+        }
+        assert_eq!(g.nodes[0].price_history.len(), super::PRICE_HISTORY_LENGTH);
+    }
+}
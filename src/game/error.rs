@@ -0,0 +1,216 @@
+use crate::game::building::BuildingKind;
+use crate::game::money::{Account, Money};
+use crate::game::BuildingId;
+use std::fmt;
+
+/// reasons for a game operation to be rejected; the game state is left untouched
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameError {
+    NoUncolonizedNode,
+    NodeAlreadyColonized(usize),
+    InvalidNodeIndex(usize),
+    InvalidCharacterIndex(usize),
+    InvalidNationIndex(usize),
+    InvalidCorporationIndex(usize),
+    CharacterNotDeployed(usize),
+    NoRoute {
+        from: usize,
+        to: usize,
+    },
+    NoOrders(usize), // character id
+    CharacterNotParked(usize),
+    CharacterNotInNode {
+        character_idx: usize,
+        node_idx: usize,
+    },
+    TileOutsideNode {
+        tile: (u32, u32),
+    },
+    TileOccupied {
+        tile: (u32, u32),
+        building: BuildingId,
+    },
+    UnknownRecipe(String),
+    NodeNotColonized(usize),
+    BuildingLimitReached {
+        kind: BuildingKind,
+        node_idx: usize,
+        limit: usize,
+    },
+    NoBuildingSite {
+        kind: BuildingKind,
+        node_idx: usize,
+    },
+    NotABusiness(BuildingKind),
+    OnlyForCorporations(BuildingKind),
+    NotACeo(usize), // character id
+    HQAlreadyBuilt {
+        corporation_idx: usize,
+        hq: BuildingId,
+    },
+    NotEnoughShares {
+        corporation_idx: usize,
+        character_idx: usize,
+        owned: u32,
+    },
+    TooManyShares {
+        corporation_idx: usize,
+        issued: u32,
+    },
+    InsufficientFunds {
+        account: Account,
+        cost: Money,
+        funds: Money,
+    },
+    NoSuchBuilding(BuildingId), // never built, or demolished
+    NoBuildingAt {
+        tile: (u32, u32),
+    },
+    NotDemolishable(BuildingId),
+    NotAWarehouse(BuildingId),
+    InvalidPrice(f32),
+    InvalidBudgetRate(f32),
+    BudgetExceeded {
+        node_idx: usize,
+        total: f32, // sum of the budget rates of the node's institutions
+    },
+    NotAnIndustry(BuildingId),
+    NotAProducer(BuildingId),
+    RecipeNotForBuilding {
+        recipe: String,
+        building: BuildingId,
+    },
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::NoUncolonizedNode => write!(f, "every node has been colonized"),
+            GameError::NodeAlreadyColonized(node_idx) => {
+                write!(f, "node {} is already colonized", node_idx)
+            }
+            GameError::InvalidNodeIndex(node_idx) => write!(f, "no node {}", node_idx),
+            GameError::InvalidCharacterIndex(character_idx) => {
+                write!(f, "no character {}", character_idx)
+            }
+            GameError::InvalidNationIndex(nation_idx) => write!(f, "no nation {}", nation_idx),
+            GameError::InvalidCorporationIndex(corporation_idx) => {
+                write!(f, "no corporation {}", corporation_idx)
+            }
+            GameError::NoRoute { from, to } => {
+                write!(f, "no road leads from node {} to node {}", from, to)
+            }
+            GameError::NoOrders(character_idx) => {
+                write!(f, "unit {} has no orders", character_idx)
+            }
+            GameError::CharacterNotDeployed(character_idx) => {
+                write!(f, "character {} isn't deployed", character_idx)
+            }
+            GameError::CharacterNotParked(character_idx) => {
+                write!(f, "character {} isn't parked in a city", character_idx)
+            }
+            GameError::CharacterNotInNode {
+                character_idx,
+                node_idx,
+            } => write!(
+                f,
+                "character {} isn't parked in node {}",
+                character_idx, node_idx
+            ),
+            GameError::TileOutsideNode { tile: (x, y) } => {
+                write!(f, "tile ({},{}) isn't inside the same node", x, y)
+            }
+            GameError::TileOccupied {
+                tile: (x, y),
+                building,
+            } => write!(f, "tile ({},{}) is occupied by {:?}", x, y, building),
+            GameError::UnknownRecipe(name) => write!(f, "no recipe {}", name),
+            GameError::NodeNotColonized(node_idx) => {
+                write!(f, "node {} isn't colonized", node_idx)
+            }
+            GameError::BuildingLimitReached {
+                kind,
+                node_idx,
+                limit,
+            } => write!(
+                f,
+                "node {} already has {} {} (the limit per city)",
+                node_idx, limit, kind
+            ),
+            GameError::NoBuildingSite { kind, node_idx } => {
+                write!(f, "no room for a {} in node {}", kind, node_idx)
+            }
+            GameError::NotABusiness(kind) => write!(f, "a {} isn't a business", kind),
+            GameError::OnlyForCorporations(kind) => {
+                write!(f, "only corporations can build a {}", kind)
+            }
+            GameError::NotACeo(character_idx) => {
+                write!(f, "character {} doesn't lead a corporation", character_idx)
+            }
+            GameError::HQAlreadyBuilt {
+                corporation_idx,
+                hq,
+            } => write!(
+                f,
+                "corporation {} already has its headquarters in {:?}",
+                corporation_idx, hq
+            ),
+            GameError::NotEnoughShares {
+                corporation_idx,
+                character_idx,
+                owned,
+            } => write!(
+                f,
+                "character {} only owns {} shares of corporation {}",
+                character_idx, owned, corporation_idx
+            ),
+            GameError::TooManyShares {
+                corporation_idx,
+                issued,
+            } => write!(
+                f,
+                "corporation {} already issued {} shares, and cannot issue that many more",
+                corporation_idx, issued
+            ),
+            GameError::InsufficientFunds {
+                account,
+                cost,
+                funds,
+            } => write!(f, "{} can't pay {} with {}", account, cost, funds),
+            GameError::NoSuchBuilding(building) => write!(f, "no building {:?}", building),
+            GameError::NoBuildingAt { tile: (x, y) } => {
+                write!(f, "no building at ({},{})", x, y)
+            }
+            GameError::NotDemolishable(building) => {
+                write!(f, "{:?} cannot be demolished", building)
+            }
+            GameError::NotAWarehouse(building) => write!(f, "{:?} isn't a warehouse", building),
+            GameError::InvalidPrice(price) => {
+                write!(f, "{} isn't a valid price (must be 0 or more)", price)
+            }
+            GameError::InvalidBudgetRate(rate) => {
+                write!(
+                    f,
+                    "{} isn't a valid budget rate (must be from 0 to 1)",
+                    rate
+                )
+            }
+            GameError::BudgetExceeded { node_idx, total } => write!(
+                f,
+                "budgets of node {} would add up to {} (must be at most 1)",
+                node_idx, total
+            ),
+            GameError::NotAProducer(building) => {
+                write!(f, "{:?} isn't a farm, mine, workshop or factory", building)
+            }
+            GameError::NotAnIndustry(building) => {
+                write!(f, "{:?} isn't a mine, workshop or factory", building)
+            }
+            GameError::RecipeNotForBuilding { recipe, building } => {
+                write!(f, "recipe {} can't run in {:?}", recipe, building)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
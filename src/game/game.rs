@@ -1,16 +1,17 @@
+use crate::game::building::BuildingKind;
+use crate::game::corporation::STARTING_SAVINGS;
+use crate::game::money::{Account, Money, TransactionKind, STARTING_FUNDS};
 use crate::game::BuildingId;
 use crate::game::Character;
 use crate::game::CharacterMobility;
-use crate::game::ColonyShipLanding;
 use crate::game::Entities;
 use crate::game::Entity;
-use crate::game::Farm;
 use crate::game::Game;
+use crate::game::GameError;
 use crate::game::MovableUnit;
-use crate::game::Nation;
-use crate::game::Warehouse;
 use crate::geometry::circle::Circle;
 use crate::geometry::circle_rectangle;
+use crate::geometry::rectangle::Rectangle;
 use nalgebra::Point2;
 use ordered_float::OrderedFloat;
 use rand::prelude::SliceRandom;
@@ -23,36 +24,32 @@ impl Entities {
     pub fn unit_comparator_by_x(&self, a: usize, b: usize) -> std::cmp::Ordering {
         let characters = &self.characters;
 
-        let a_x = OrderedFloat(characters[a].get_x());
-        let b_x = OrderedFloat(characters[b].get_x());
+        let a_x = characters[a].get_x().map(OrderedFloat);
+        let b_x = characters[b].get_x().map(OrderedFloat);
         a_x.cmp(&b_x)
     }
 
     pub fn unit_comparator_by_y(&self, a: usize, b: usize) -> std::cmp::Ordering {
         let characters = &self.characters;
 
-        let a_y = OrderedFloat(characters[a].get_y());
-        let b_y = OrderedFloat(characters[b].get_y());
+        let a_y = characters[a].get_y().map(OrderedFloat);
+        let b_y = characters[b].get_y().map(OrderedFloat);
         a_y.cmp(&b_y)
     }
 }
 
 impl Entity for Character {
-    fn get_x(&self) -> f32 {
+    fn get_x(&self) -> Option<f32> {
         match self.mobility {
-            CharacterMobility::Unit { body, .. } => body.center.x,
-            CharacterMobility::Parked { .. } => {
-                unreachable!("caller should filter out parked character")
-            }
+            CharacterMobility::Unit { body, .. } => Some(body.center.x),
+            CharacterMobility::Parked { .. } => None,
         }
     }
 
-    fn get_y(&self) -> f32 {
+    fn get_y(&self) -> Option<f32> {
         match self.mobility {
-            CharacterMobility::Unit { body, .. } => body.center.y,
-            CharacterMobility::Parked { .. } => {
-                unreachable!("caller should filter out parked character")
-            }
+            CharacterMobility::Unit { body, .. } => Some(body.center.y),
+            CharacterMobility::Parked { .. } => None,
         }
     }
 }
@@ -74,26 +71,61 @@ impl Game {
         !self.nodes[node_idx].buildings.is_empty()
     }
 
-    fn can_build_at(&self, top_left: [u32; 2], width: u32, height: u32) -> bool {
-        //
+    /// the node that a building stands in
+    pub fn node_of_building(&self, building_id: BuildingId) -> Option<usize> {
+        self.nodes
+            .iter()
+            .position(|node| node.buildings.contains(&building_id))
+    }
+
+    pub(crate) fn check_node_idx(&self, node_idx: usize) -> Result<(), GameError> {
+        if node_idx < self.nodes.len() {
+            Ok(())
+        } else {
+            Err(GameError::InvalidNodeIndex(node_idx))
+        }
+    }
+
+    pub(crate) fn check_character_idx(&self, character_idx: usize) -> Result<(), GameError> {
+        if character_idx < self.entities.characters.len() {
+            Ok(())
+        } else {
+            Err(GameError::InvalidCharacterIndex(character_idx))
+        }
+    }
+
+    /// check whether a building fits at the tiles; returns the node that will own the building
+    pub(crate) fn check_build_site(
+        &self,
+        top_left: [u32; 2],
+        width: u32,
+        height: u32,
+    ) -> Result<usize, GameError> {
         let [tl_x, tl_y] = top_left;
 
-        if let Some(target_node_idx) = self.search_node_by_coor([tl_x as f32, tl_y as f32]) {
-            if self.are_tiles_in_same_node(top_left, target_node_idx, width, height) {
-                for x in 0..width {
-                    let x = x + tl_x;
-                    for y in 0..height {
-                        let y = y + tl_y;
-                        if self.building_grid.contains_key(&(x, y)) {
-                            return false;
-                        }
-                    }
+        let target_node_idx = self
+            .search_node_by_coor([tl_x as f32, tl_y as f32])
+            .ok_or(GameError::TileOutsideNode { tile: (tl_x, tl_y) })?;
+
+        for x in 0..width {
+            let x = x + tl_x;
+            for y in 0..height {
+                let y = y + tl_y;
+
+                if self.search_node_by_coor([x as f32, y as f32]) != Some(target_node_idx) {
+                    return Err(GameError::TileOutsideNode { tile: (x, y) });
+                }
+
+                if let Some(&building) = self.building_grid.get(&(x, y)) {
+                    return Err(GameError::TileOccupied {
+                        tile: (x, y),
+                        building,
+                    });
                 }
-                return true;
             }
         }
 
-        false
+        Ok(target_node_idx)
     }
 
     fn search_node_by_coor(&self, [x, y]: [f32; 2]) -> Option<usize> {
@@ -110,7 +142,7 @@ impl Game {
         None
     }
 
-    pub fn colonize(&mut self) -> Option<BuildingId> {
+    pub fn colonize(&mut self) -> Result<BuildingId, GameError> {
         // find an uncolonized node
         // add a nation tag
         // add the landing building, starting population and elites
@@ -121,33 +153,43 @@ impl Game {
             .filter(|&i| self.nodes[i].buildings.is_empty())
             .collect();
 
-        if uncolonized_nodes.is_empty() {
-            panic!("panic for now");
-            // return None;
-        }
-
         let &node_idx = uncolonized_nodes
             .choose(&mut self.rng)
-            .expect("slice already checked to be unempty");
-
-        let ret = self.place_colony_ship_landing(node_idx);
-        assert!(ret.is_some());
+            .ok_or(GameError::NoUncolonizedNode)?;
+
+        let ret = self.place_colony_ship_landing(node_idx)?;
+        if let Err(err) = self.place_market(node_idx) {
+            // leave the node uncolonized, as it was
+            self.remove_building(ret);
+            self.nodes[node_idx].housing = Default::default();
+            return Err(err);
+        }
 
         // random generate a nation for now(?)
 
         // wanted: create "colony ship" (not physically in game) with starting information and then pass information here
 
-        let nation = Nation {
-            cities: Default::default(),
-        };
-
-        self.nations.push(nation);
+        let nation_idx = self.found_nation(node_idx);
+        self.ledger.transfer(
+            self.tick,
+            TransactionKind::Grant,
+            Account::World,
+            Account::Nation(nation_idx),
+            Money::from_credits(STARTING_FUNDS),
+        );
 
         // create people
 
         let num_starting_colonists = 10;
 
         for _ in 0..num_starting_colonists {
+            self.ledger.transfer(
+                self.tick,
+                TransactionKind::Grant,
+                Account::World,
+                Account::Character(self.entities.characters.len()),
+                Money::from_credits(STARTING_SAVINGS),
+            );
             self.entities.characters.push(Character {
                 children: Default::default(),
                 charisma: 0,
@@ -158,14 +200,16 @@ impl Game {
             });
         }
 
-        ret
+        Ok(ret)
     }
 
-    pub fn place_colony_ship_landing(&mut self, node_idx: usize) -> Option<BuildingId> {
-        const WIDTH: u32 = 3;
-        const HEIGHT: u32 = 3;
-        assert!(!self.is_colonized(node_idx));
+    pub fn place_colony_ship_landing(&mut self, node_idx: usize) -> Result<BuildingId, GameError> {
+        self.check_node_idx(node_idx)?;
+        if self.is_colonized(node_idx) {
+            return Err(GameError::NodeAlreadyColonized(node_idx));
+        }
 
+        // centered on the node
         let center_f32 = self.nodes[node_idx].coor;
         let [x, y] = [center_f32.x, center_f32.y];
         let [xu32, yu32] = [x as u32, y as u32];
@@ -174,55 +218,115 @@ impl Game {
             let y = if yu32 == 0 { 0 } else { yu32 - 1 };
             [x, y]
         };
-        let top_left_f32 = [top_left_u32[0] as f32, top_left_u32[1] as f32];
 
-        if self.can_build_at(top_left_u32, WIDTH, HEIGHT) {
-            if let Some(target_node_idx) = self.search_node_by_coor(top_left_f32) {
-                if self.are_tiles_in_same_node(top_left_u32, target_node_idx, WIDTH, HEIGHT) {
-                    let landing = ColonyShipLanding {};
-                    let buildings = &mut self.entities.buildings;
-                    let building_id = BuildingId::ColonyShipLanding(buildings.landings.len());
-                    buildings.landings.push(landing);
+        let kind = BuildingKind::ColonyShipLanding;
+        let (width, height) = kind.spec().footprint;
+        let target_node_idx = self.check_build_site(top_left_u32, width, height)?;
 
-                    self.bulk_insert_building_grid(top_left_u32, building_id, WIDTH, HEIGHT);
-
-                    let is_inserted = self.nodes[target_node_idx].buildings.insert(building_id);
-                    assert!(is_inserted);
+        Ok(self.insert_building(kind, top_left_u32, target_node_idx))
+    }
 
-                    return Some(building_id);
-                }
-            }
+    /// build in a colonized node, following the spec of the kind; the nation that owns the city
+    /// pays for the building
+    pub fn create_building(
+        &mut self,
+        kind: BuildingKind,
+        top_left: [u32; 2],
+    ) -> Result<BuildingId, GameError> {
+        if kind == BuildingKind::CorpHQ {
+            return Err(GameError::OnlyForCorporations(kind));
         }
 
-        None
+        let target_node_idx = self.check_construction(kind, top_left)?;
+        let payer = match self.nation_of_node(target_node_idx) {
+            Some(nation_idx) => Account::Nation(nation_idx),
+            None => Account::City(target_node_idx),
+        };
+        self.pay_and_insert_building(kind, top_left, target_node_idx, payer)
     }
 
-    fn are_tiles_in_same_node(
+    /// check everything but the funds; returns the node that will own the building
+    pub(crate) fn check_construction(
         &self,
+        kind: BuildingKind,
         top_left: [u32; 2],
-        target_node_idx: usize,
-        width: u32,
-        height: u32,
-    ) -> bool {
-        let [tl_x, tl_y] = top_left;
-        for x in 0..width {
-            let x = x + tl_x;
-            for y in 0..height {
-                let y = y + tl_y;
-                if let Some(node_idx) = self.search_node_by_coor([x as f32, y as f32]) {
-                    if node_idx == target_node_idx {
-                        // (x,y) *is* not part of the node
-                        continue;
-                    }
-                // (x,y) is not part of the node
-                } else {
-                    // (x,y) is not part of the node
-                }
-                // (x,y) is not part of the node
-                return false;
+    ) -> Result<usize, GameError> {
+        let spec = kind.spec();
+        let (width, height) = spec.footprint;
+
+        let target_node_idx = self.check_build_site(top_left, width, height)?;
+
+        if !self.is_colonized(target_node_idx) {
+            return Err(GameError::NodeNotColonized(target_node_idx));
+        }
+
+        if let Some(limit) = spec.per_city_limit {
+            let num_built = self.nodes[target_node_idx]
+                .buildings
+                .iter()
+                .filter(|building| building.kind() == kind)
+                .count();
+            if num_built >= limit {
+                return Err(GameError::BuildingLimitReached {
+                    kind,
+                    node_idx: target_node_idx,
+                    limit,
+                });
             }
         }
-        true
+
+        Ok(target_node_idx)
+    }
+
+    /// charge the payer the cost of a building that passed check_construction, then build it
+    pub(crate) fn pay_and_insert_building(
+        &mut self,
+        kind: BuildingKind,
+        top_left: [u32; 2],
+        node_idx: usize,
+        payer: Account,
+    ) -> Result<BuildingId, GameError> {
+        let cost = Money::from_credits(kind.spec().cost as f32);
+        let funds = self.ledger.balance(payer);
+        if funds < cost {
+            return Err(GameError::InsufficientFunds {
+                account: payer,
+                cost,
+                funds,
+            });
+        }
+
+        self.ledger.transfer(
+            self.tick,
+            TransactionKind::Construction,
+            payer,
+            Account::World,
+            cost,
+        );
+        Ok(self.insert_building(kind, top_left, node_idx))
+    }
+
+    pub(crate) fn insert_building(
+        &mut self,
+        kind: BuildingKind,
+        top_left: [u32; 2],
+        node_idx: usize,
+    ) -> BuildingId {
+        let (width, height) = kind.spec().footprint;
+
+        let building_id = self.entities.buildings.insert(kind);
+
+        self.bulk_insert_building_grid(top_left, building_id, width, height);
+        self.building_sites.insert(building_id, top_left);
+
+        let node = &mut self.nodes[node_idx];
+        let is_inserted = node.buildings.insert(building_id);
+        assert!(is_inserted);
+        if kind.housing_capacity() > 0 {
+            node.house_people(&mut self.entities.buildings);
+        }
+
+        building_id
     }
 
     fn bulk_insert_building_grid(
@@ -243,66 +347,108 @@ impl Game {
         }
     }
 
-    pub fn create_farm(&mut self, top_left: [u32; 2]) -> Option<BuildingId> {
-        let [tl_x, tl_y] = top_left;
-        const WIDTH: u32 = 2;
-        const HEIGHT: u32 = 2;
+    pub fn create_farm(&mut self, top_left: [u32; 2]) -> Result<BuildingId, GameError> {
+        self.create_building(BuildingKind::Farm, top_left)
+    }
+
+    pub fn create_warehouse(&mut self, top_left: [u32; 2]) -> Result<BuildingId, GameError> {
+        self.create_building(BuildingKind::Warehouse, top_left)
+    }
 
-        if self.can_build_at(top_left, WIDTH, HEIGHT) {
-            if let Some(target_node_idx) = self.search_node_by_coor([tl_x as f32, tl_y as f32]) {
-                if self.are_tiles_in_same_node(top_left, target_node_idx, WIDTH, HEIGHT) {
-                    let farm = Farm { output: 0 };
-                    let buildings = &mut self.entities.buildings;
-                    let building_id = BuildingId::Farm(buildings.farms.len());
-                    buildings.farms.push(farm);
+    /// build the workplace of a recipe, which runs it every tick
+    pub fn create_industry(
+        &mut self,
+        top_left: [u32; 2],
+        recipe: &str,
+    ) -> Result<BuildingId, GameError> {
+        let workplace = self
+            .recipes
+            .get(recipe)
+            .ok_or_else(|| GameError::UnknownRecipe(recipe.to_owned()))?
+            .workplace;
+
+        let building_id = self.create_building(workplace.building_kind(), top_left)?;
+        self.set_recipe(building_id, recipe)
+            .expect("bug: the recipe fits the building");
+
+        Ok(building_id)
+    }
 
-                    self.bulk_insert_building_grid(top_left, building_id, WIDTH, HEIGHT);
+    /// remove a building and free its tiles; its id never becomes valid again
+    pub fn demolish(&mut self, building_id: BuildingId) -> Result<(), GameError> {
+        if !self.entities.buildings.contains(building_id) {
+            return Err(GameError::NoSuchBuilding(building_id));
+        }
+        if !building_id.kind().spec().demolishable {
+            return Err(GameError::NotDemolishable(building_id));
+        }
 
-                    let is_inserted = self.nodes[target_node_idx].buildings.insert(building_id);
-                    assert!(is_inserted);
+        self.remove_building(building_id);
+        Ok(())
+    }
 
-                    return Some(building_id);
-                }
+    /// take a building off the map, whatever its kind
+    fn remove_building(&mut self, building_id: BuildingId) {
+        self.entities.buildings.remove(building_id);
+        for corporation in &mut self.corporations {
+            corporation.remove_building(building_id);
+        }
+        self.building_grid
+            .retain(|_, &mut building| building != building_id);
+        self.building_sites.remove(&building_id);
+        for node in &mut self.nodes {
+            if node.buildings.remove(&building_id) && building_id.kind().housing_capacity() > 0 {
+                node.house_people(&mut self.entities.buildings);
             }
         }
+    }
 
-        None
+    pub fn demolish_at(&mut self, tile: (u32, u32)) -> Result<BuildingId, GameError> {
+        let &building_id = self
+            .building_grid
+            .get(&tile)
+            .ok_or(GameError::NoBuildingAt { tile })?;
+        self.demolish(building_id)?;
+        Ok(building_id)
     }
 
-    pub fn create_warehouse(&mut self, top_left: [u32; 2]) -> Option<BuildingId> {
-        let [tl_x, tl_y] = top_left;
-        const WIDTH: u32 = 2;
-        const HEIGHT: u32 = 2;
+    /// change what a mine, workshop or factory produces
+    pub fn set_recipe(&mut self, building_id: BuildingId, recipe: &str) -> Result<(), GameError> {
+        if !self.entities.buildings.contains(building_id) {
+            return Err(GameError::NoSuchBuilding(building_id));
+        }
 
-        if self.can_build_at(top_left, WIDTH, HEIGHT) {
-            if let Some(target_node_idx) = self.search_node_by_coor([tl_x as f32, tl_y as f32]) {
-                if self.are_tiles_in_same_node(top_left, target_node_idx, WIDTH, HEIGHT) {
-                    let warehouse = Warehouse::default();
-                    let buildings = &mut self.entities.buildings;
-                    let building_id = BuildingId::Warehouse(buildings.warehouses.len());
-                    buildings.warehouses.push(warehouse);
+        if self.entities.buildings.industry_mut(building_id).is_none() {
+            return Err(GameError::NotAnIndustry(building_id));
+        }
 
-                    self.bulk_insert_building_grid(top_left, building_id, WIDTH, HEIGHT);
+        let workplace = self
+            .recipes
+            .get(recipe)
+            .ok_or_else(|| GameError::UnknownRecipe(recipe.to_owned()))?
+            .workplace;
 
-                    let is_inserted = self.nodes[target_node_idx].buildings.insert(building_id);
-                    assert!(is_inserted);
+        if workplace.building_kind() != building_id.kind() {
+            return Err(GameError::RecipeNotForBuilding {
+                recipe: recipe.to_owned(),
+                building: building_id,
+            });
+        }
 
-                    return Some(building_id);
-                }
-            }
+        if let Some(industry) = self.entities.buildings.industry_mut(building_id) {
+            industry.recipe = Some(recipe.to_owned());
         }
 
-        None
+        Ok(())
     }
 
-    pub fn get_unit_coor(&self, idx: usize) -> Point2<f32> {
+    pub fn get_unit_coor(&self, idx: usize) -> Result<Point2<f32>, GameError> {
+        self.check_character_idx(idx)?;
         let character = &self.entities.characters[idx];
 
         match character.mobility {
-            CharacterMobility::Parked { .. } => {
-                unreachable!("only deployed characters should be part of the index")
-            }
-            CharacterMobility::Unit { body, .. } => body.center,
+            CharacterMobility::Parked { .. } => Err(GameError::CharacterNotDeployed(idx)),
+            CharacterMobility::Unit { body, .. } => Ok(body.center),
         }
     }
 
@@ -322,14 +468,20 @@ impl Game {
         );
 
         let x_lower_bound = match self.unit_x_axis.binary_search_by(|idx| {
-            OrderedFloat(self.entities.characters[*idx].get_x()).cmp(&x_left)
+            self.entities.characters[*idx]
+                .get_x()
+                .map(OrderedFloat)
+                .cmp(&Some(x_left))
         }) {
             Ok(idx) => idx,
             Err(idx) => idx,
         };
 
         let x_upper_bound = match self.unit_x_axis.binary_search_by(|idx| {
-            OrderedFloat(self.entities.characters[*idx].get_x()).cmp(&x_right)
+            self.entities.characters[*idx]
+                .get_x()
+                .map(OrderedFloat)
+                .cmp(&Some(x_right))
         }) {
             Ok(idx) => idx,
             Err(idx) => idx,
@@ -338,20 +490,26 @@ impl Game {
         assert!(x_lower_bound <= x_upper_bound);
 
         let y_lower_bound = match self.unit_y_axis.binary_search_by(|idx| {
-            OrderedFloat(self.entities.characters[*idx].get_y()).cmp(&y_left)
+            self.entities.characters[*idx]
+                .get_y()
+                .map(OrderedFloat)
+                .cmp(&Some(y_left))
         }) {
             Ok(idx) => idx,
             Err(idx) => idx,
         };
 
         let y_upper_bound = match self.unit_y_axis.binary_search_by(|idx| {
-            OrderedFloat(self.entities.characters[*idx].get_y()).cmp(&y_right)
+            self.entities.characters[*idx]
+                .get_y()
+                .map(OrderedFloat)
+                .cmp(&Some(y_right))
         }) {
             Ok(idx) => idx,
             Err(idx) => idx,
         };
 
-        println!(
+        debug!(
             "target:{}, radius:{}, x_low:{}, x_high:{}, y_low:{}, y_high:{}",
             target, radius, x_lower_bound, x_upper_bound, y_lower_bound, y_upper_bound
         );
@@ -379,33 +537,53 @@ impl Game {
                 .filter(|idx| {
                     // refine search
 
-                    if let CharacterMobility::Unit { body, .. } =
-                        self.entities.characters[*idx].mobility
-                    {
-                        circle_rectangle::is_intersect(&body, &search_area)
-                    } else {
-                        unreachable!("undeployed characters shouldn't be part of the index");
+                    match self.entities.characters[*idx].mobility {
+                        CharacterMobility::Unit { body, .. } => {
+                            circle_rectangle::is_intersect(&body, &search_area)
+                        }
+                        CharacterMobility::Parked { .. } => false,
                     }
                 })
                 .collect()
         }
     }
 
-    pub fn set_unit_destination(&mut self, character_idx: usize, city_idx: usize) {
-        assert!(character_idx < self.entities.characters.len());
-        assert!(city_idx < self.nodes.len());
-
-        // TODO remove me later
-        {
-            let character = &self.entities.characters[character_idx];
-            match character.mobility {
-                CharacterMobility::Parked { .. } => unimplemented!("TODO not right now"),
-                CharacterMobility::Unit { .. } => {
-                    // Ok
-                }
-            }
-        }
+    /// turn a character parked in a city into a unit on the map
+    pub fn deploy_character(&mut self, character_idx: usize) -> Result<(), GameError> {
+        self.check_character_idx(character_idx)?;
+
+        let character = &mut self.entities.characters[character_idx];
+
+        if let CharacterMobility::Parked { node_idx } = character.mobility {
+            let coor = self.nodes[node_idx].coor;
+            character.mobility = CharacterMobility::Unit {
+                body: Rectangle {
+                    length: 1.,
+                    width: 1.,
+                    center: coor,
+                    angle: 0.,
+                },
+                character_idx,
+            };
 
-        self.unit_destinations.insert(character_idx, city_idx);
+            self.unit_x_axis.push(character_idx);
+            self.unit_y_axis.push(character_idx);
+            Ok(())
+        } else {
+            Err(GameError::CharacterNotParked(character_idx))
+        }
     }
 }
+
+/// a test game whose colony ship has landed, and the node it landed in
+#[cfg(test)]
+pub(crate) fn colonized_game(starting_population: usize) -> (Game, usize) {
+    let mut g = crate::GameOptions::default()
+        .set_seed(1234)
+        .set_starting_population(starting_population)
+        .build()
+        .unwrap();
+    g.colonize().unwrap();
+    let node_idx = (0..g.nodes.len()).find(|&idx| g.is_colonized(idx)).unwrap();
+    (g, node_idx)
+}
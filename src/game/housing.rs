@@ -0,0 +1,214 @@
+use crate::game::building::BuildingKind;
+use crate::game::BuildingId;
+use crate::game::Buildings;
+use crate::game::Household;
+use crate::game::Housing;
+use crate::game::Node;
+
+/*
+People live in the households of a node: houses, skyscrapers, and the quarters of the colony ship
+landing. Whenever the population or the buildings change, people move in building by building:
+- first up to the capacity of every household
+- then, overcrowded, up to OVERCROWDING times the capacity
+- whoever is left is homeless
+
+Overcrowding and homelessness lower the satisfaction of the node, which lowers its birth rate, and
+no one is born once every household is full to the point of overcrowding (see population.rs).
+*/
+
+/// residents of a house
+pub const HOUSE_CAPACITY: u32 = 10;
+
+/// residents of a skyscraper
+pub const SKYSCRAPER_CAPACITY: u32 = 200;
+
+/// residents of the quarters of a colony ship landing
+pub const LANDING_CAPACITY: u32 = 150;
+
+/// households take up to this many times their capacity, overcrowded
+pub const OVERCROWDING: f32 = 1.5;
+
+/// loss of satisfaction for each overcrowded person, relative to a homeless person
+const OVERCROWDED_PENALTY: f32 = 0.5;
+
+impl BuildingKind {
+    /// residents that a building of this kind houses, without overcrowding
+    pub fn housing_capacity(self) -> u32 {
+        match self {
+            BuildingKind::House => HOUSE_CAPACITY,
+            BuildingKind::Skyscraper => SKYSCRAPER_CAPACITY,
+            BuildingKind::ColonyShipLanding => LANDING_CAPACITY,
+            _ => 0,
+        }
+    }
+}
+
+impl Household {
+    pub fn num_people(&self) -> u32 {
+        self.num_people
+    }
+}
+
+impl Buildings {
+    /// the household of a house, a skyscraper or a landing
+    pub fn household(&self, building: BuildingId) -> Option<&Household> {
+        match building {
+            BuildingId::House(handle) => self.houses.get(handle),
+            BuildingId::Skyscraper(handle) => self.skyscrapers.get(handle),
+            BuildingId::ColonyShipLanding(handle) => {
+                self.landings.get(handle).map(|landing| &landing.quarters)
+            }
+            _ => None,
+        }
+    }
+
+    fn household_mut(&mut self, building: BuildingId) -> Option<&mut Household> {
+        match building {
+            BuildingId::House(handle) => self.houses.get_mut(handle),
+            BuildingId::Skyscraper(handle) => self.skyscrapers.get_mut(handle),
+            BuildingId::ColonyShipLanding(handle) => self
+                .landings
+                .get_mut(handle)
+                .map(|landing| &mut landing.quarters),
+            _ => None,
+        }
+    }
+}
+
+impl Housing {
+    pub fn population(&self) -> usize {
+        self.housed + self.overcrowded + self.homeless
+    }
+
+    /// most people that the households take, overcrowded
+    pub fn max_residents(&self) -> usize {
+        (self.capacity as f32 * OVERCROWDING) as usize
+    }
+
+    /// from 0 when everybody is homeless, to 1 when everybody is housed
+    pub fn satisfaction(&self) -> f32 {
+        let population = self.population();
+        if population == 0 {
+            return 1.;
+        }
+        let unhappy = OVERCROWDED_PENALTY * self.overcrowded as f32 + self.homeless as f32;
+        1. - unhappy / population as f32
+    }
+}
+
+impl Node {
+    /// households of the node, in order
+    pub fn households(&self) -> Vec<BuildingId> {
+        let mut households: Vec<_> = self
+            .buildings
+            .iter()
+            .cloned()
+            .filter(|building| building.kind().housing_capacity() > 0)
+            .collect();
+        households.sort();
+        households
+    }
+
+    /// move the population into the households, after it or the buildings changed
+    pub(crate) fn house_people(&mut self, buildings: &mut Buildings) {
+        let households = self.households();
+        let mut housing = Housing::default();
+        let mut left = self.population;
+
+        for &building in &households {
+            let capacity = building.kind().housing_capacity() as usize;
+            let residents = left.min(capacity);
+            housing.capacity += capacity;
+            housing.housed += residents;
+            left -= residents;
+            if let Some(household) = buildings.household_mut(building) {
+                household.num_people = residents as u32;
+            }
+        }
+
+        for &building in &households {
+            let capacity = building.kind().housing_capacity();
+            let extra = (capacity as f32 * (OVERCROWDING - 1.)) as usize;
+            let residents = left.min(extra);
+            housing.overcrowded += residents;
+            left -= residents;
+            if let Some(household) = buildings.household_mut(building) {
+                household.num_people += residents as u32;
+            }
+        }
+
+        housing.homeless = left;
+        self.housing = housing;
+    }
+}
+
+#[cfg(test)]
+mod housing_tests {
+    use super::*;
+    use crate::game::game::colonized_game;
+
+    #[test]
+    fn people_fill_households_then_overcrowd_them() {
+        let (mut g, node_idx) = colonized_game(100);
+        let landing = *g.nodes[node_idx]
+            .buildings
+            .iter()
+            .find(|building| building.kind() == BuildingKind::ColonyShipLanding)
+            .unwrap();
+        let coor = g.nodes[node_idx].coor;
+        let [x, y] = [coor.x as u32, coor.y as u32];
+        let house = g.create_building(BuildingKind::House, [x + 3, y]).unwrap();
+
+        // the landing comes first, and has room for everybody
+        let node = &g.nodes[node_idx];
+        assert_eq!(node.households(), vec![landing, house]);
+        assert_eq!(node.housing.capacity, 160);
+        assert_eq!(node.housing.satisfaction(), 1.);
+        assert_eq!(
+            g.entities.buildings.household(house).unwrap().num_people(),
+            0
+        );
+
+        let node = &mut g.nodes[node_idx];
+        node.population = 250;
+        node.house_people(&mut g.entities.buildings);
+        let housing = node.housing;
+        assert_eq!(
+            (housing.housed, housing.overcrowded, housing.homeless),
+            (160, 80, 10)
+        );
+        assert_eq!(housing.max_residents(), 240);
+        assert_eq!(
+            g.entities
+                .buildings
+                .household(landing)
+                .unwrap()
+                .num_people(),
+            150 + 75
+        );
+        assert_eq!(
+            g.entities.buildings.household(house).unwrap().num_people(),
+            15
+        );
+        assert_eq!(housing.satisfaction(), 1. - (40. + 10.) / 250.);
+
+        // demolished households send their residents back into the others
+        g.demolish(house).unwrap();
+        let housing = g.nodes[node_idx].housing;
+        assert_eq!(housing.capacity, 150);
+        assert_eq!(housing.homeless, 25);
+    }
+
+    #[test]
+    fn full_households_stop_growth() {
+        let (mut g, node_idx) = colonized_game(225);
+        g.nodes[node_idx].market[crate::game::Commodity::Grain] = 10_000;
+
+        for _ in 0..50 {
+            g.update_state();
+        }
+        let node = &g.nodes[node_idx];
+        assert!(node.population <= node.housing.max_residents());
+        assert_eq!(node.housing.homeless, 0);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::game::money::{Account, Ledger, Money, TransactionKind};
+use crate::game::Character;
+use crate::game::CharacterMobility;
+use crate::game::Game;
+use crate::game::GameError;
+use crate::game::Institution;
+use crate::game::InstitutionData;
+use crate::game::Nation;
+use crate::game::Node;
+
+/*
+Every tick, a city spends a share of its cash on each institution, following the budget rates.
+The spending sets the capacity of the institution, and its actual value moves towards the
+capacity, one step per tick: institutions take time to build up, and to wind down.
+
+Effects grow with the actual value, from 0 towards 1 (see InstitutionData::effect):
+- Health and Welfare: see population.rs
+- Safety and Law: less of the city's cash is stolen every tick
+- Education: characters parked in the city grow smarter
+- Research: the nation that owns the city makes tech progress
+- Military: a share of the population serves in the militia
+*/
+
+/// actual value at which an institution has half of its full effect
+pub const INSTITUTION_REFERENCE: f32 = 100.;
+
+/// credits per tick that keep one unit of capacity running
+pub const COST_PER_CAPACITY: f32 = 0.1;
+
+/// most change of an institution's actual value in a tick
+pub const INSTITUTION_GROWTH: usize = 1;
+
+/// share of a city's cash stolen every tick, without Safety or Law
+pub const BASE_CRIME_RATE: f32 = 0.01;
+
+/// intelligence points per tick gained by each parked character, at full Education
+pub const EDUCATION_RATE: f32 = 0.1;
+
+/// tech progress per tick and per city, at full Research
+pub const RESEARCH_RATE: f32 = 1.;
+
+/// tech progress needed for every tech level
+pub const TECH_LEVEL_COST: f32 = 100.;
+
+/// share of the population in the militia, at full Military
+pub const MILITIA_SHARE: f32 = 0.1;
+
+impl Institution {
+    pub const ALL: [Institution; 7] = [
+        Institution::Health,
+        Institution::Welfare,
+        Institution::Safety,
+        Institution::Law,
+        Institution::Education,
+        Institution::Research,
+        Institution::Military,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Institution::Health => "health",
+            Institution::Welfare => "welfare",
+            Institution::Safety => "safety",
+            Institution::Law => "law",
+            Institution::Education => "education",
+            Institution::Research => "research",
+            Institution::Military => "military",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Institution> {
+        Self::ALL
+            .iter()
+            .cloned()
+            .find(|institution| institution.name() == name)
+    }
+}
+
+impl InstitutionData {
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn actual(&self) -> usize {
+        self.actual
+    }
+
+    /// share of the city's cash spent on the institution every tick
+    pub fn budget_rate(&self) -> f32 {
+        self.budget_rate
+    }
+
+    /// strength of the institution, from 0 without any actual value, towards 1
+    pub fn effect(&self) -> f32 {
+        let actual = self.actual as f32;
+        actual / (actual + INSTITUTION_REFERENCE)
+    }
+}
+
+impl Node {
+    /// share of the city's cash stolen every tick
+    pub fn crime_rate(&self) -> f32 {
+        let safety = self.institutions[Institution::Safety].effect();
+        let law = self.institutions[Institution::Law].effect();
+        BASE_CRIME_RATE * (1. - 0.5 * safety) * (1. - 0.5 * law)
+    }
+
+    pub fn militia(&self) -> usize {
+        let military = self.institutions[Institution::Military].effect();
+        (self.population as f32 * MILITIA_SHARE * military) as usize
+    }
+
+    /// fund the institutions out of the city's cash, let them grow, and let criminals steal;
+    /// returns the tech progress made by the city
+    pub(crate) fn run_institutions(
+        &mut self,
+        ledger: &mut Ledger,
+        city: Account,
+        tick: u64,
+    ) -> f32 {
+        let cash = ledger.balance(city).credits().max(0.);
+
+        for (_, institution) in self.institutions.iter_mut() {
+            let spending = Money::from_credits(cash * institution.budget_rate);
+            let kind = TransactionKind::Budget;
+            ledger.transfer(tick, kind, city, Account::World, spending);
+            institution.capacity = (spending.credits() / COST_PER_CAPACITY) as usize;
+
+            if institution.actual < institution.capacity {
+                let growth = (institution.capacity - institution.actual).min(INSTITUTION_GROWTH);
+                institution.actual += growth;
+            } else {
+                let decline = (institution.actual - institution.capacity).min(INSTITUTION_GROWTH);
+                institution.actual -= decline;
+            }
+        }
+
+        let cash = ledger.balance(city).credits().max(0.);
+        let theft = Money::from_credits(cash * self.crime_rate());
+        ledger.transfer(tick, TransactionKind::Theft, city, Account::World, theft);
+
+        self.education_progress +=
+            EDUCATION_RATE * self.institutions[Institution::Education].effect();
+
+        RESEARCH_RATE * self.institutions[Institution::Research].effect()
+    }
+
+    /// whole intelligence points earned by Education, to be given to the parked characters
+    pub(crate) fn take_education(&mut self) -> u32 {
+        let points = self.education_progress.floor();
+        self.education_progress -= points;
+        points as u32
+    }
+}
+
+impl Nation {
+    pub fn tech_level(&self) -> u32 {
+        (self.research / TECH_LEVEL_COST) as u32
+    }
+}
+
+impl Game {
+    /// institutions of every colonized node, once per tick
+    pub(crate) fn run_institutions(&mut self) {
+        for node_idx in 0..self.nodes.len() {
+            if !self.is_colonized(node_idx) {
+                continue;
+            }
+
+            let node = &mut self.nodes[node_idx];
+            let research =
+                node.run_institutions(&mut self.ledger, Account::City(node_idx), self.tick);
+            let intelligence = node.take_education();
+
+            if let Some(nation_idx) = self.nation_of_node(node_idx) {
+                self.nations[nation_idx].research += research;
+            }
+            for character in &mut self.entities.characters {
+                if is_parked_at(character, node_idx) {
+                    character.intelligence += intelligence;
+                }
+            }
+        }
+    }
+
+    /// set the share of a city's cash spent on an institution every tick
+    pub fn set_budget(
+        &mut self,
+        node_idx: usize,
+        institution: Institution,
+        rate: f32,
+    ) -> Result<(), GameError> {
+        self.check_node_idx(node_idx)?;
+        if !self.is_colonized(node_idx) {
+            return Err(GameError::NodeNotColonized(node_idx));
+        }
+        if !(0. ..=1.).contains(&rate) {
+            return Err(GameError::InvalidBudgetRate(rate));
+        }
+
+        let institutions = &mut self.nodes[node_idx].institutions;
+        let others: f32 = institutions
+            .iter()
+            .filter(|&(other, _)| other != institution)
+            .map(|(_, data)| data.budget_rate)
+            .sum();
+        if others + rate > 1. {
+            return Err(GameError::BudgetExceeded {
+                node_idx,
+                total: others + rate,
+            });
+        }
+
+        institutions[institution].budget_rate = rate;
+        Ok(())
+    }
+}
+
+fn is_parked_at(character: &Character, node_idx: usize) -> bool {
+    match character.mobility {
+        CharacterMobility::Parked { node_idx: parked } => parked == node_idx,
+        CharacterMobility::Unit { .. } => false,
+    }
+}
+
+#[cfg(test)]
+mod institution_tests {
+    use super::*;
+    use crate::game::game::colonized_game;
+
+    fn game_with_city_cash(credits: f32) -> (Game, usize) {
+        let (mut g, node_idx) = colonized_game(1000);
+        let amount = Money::from_credits(credits);
+        g.ledger.transfer(
+            0,
+            TransactionKind::Grant,
+            Account::World,
+            Account::City(node_idx),
+            amount,
+        );
+        (g, node_idx)
+    }
+
+    #[test]
+    fn budgets_build_up_institutions() {
+        let (mut g, node_idx) = game_with_city_cash(1000.);
+        g.set_budget(node_idx, Institution::Research, 0.01).unwrap();
+
+        g.update_state();
+        let research = &g.nodes[node_idx].institutions[Institution::Research];
+        // 10 credits buy a capacity of 100, which takes 100 ticks to reach
+        assert_eq!(research.capacity(), 100);
+        assert_eq!(research.actual(), 1);
+
+        for _ in 0..20 {
+            g.update_state();
+        }
+        let research = &g.nodes[node_idx].institutions[Institution::Research];
+        assert_eq!(research.actual(), 21);
+        assert!(g.nations[0].research > 0.);
+        assert_eq!(g.ledger.audit(), Ok(()));
+    }
+
+    #[test]
+    fn institutions_have_effects() {
+        let (mut g, node_idx) = game_with_city_cash(10_000.);
+        let crime_rate = g.nodes[node_idx].crime_rate();
+        for &institution in &[
+            Institution::Safety,
+            Institution::Law,
+            Institution::Education,
+            Institution::Military,
+        ] {
+            g.set_budget(node_idx, institution, 0.01).unwrap();
+        }
+
+        // spending shrinks with the city's cash, but stays above the actual values for a while
+        for _ in 0..100 {
+            g.update_state();
+        }
+        let node = &g.nodes[node_idx];
+        assert!(node.crime_rate() < crime_rate);
+        assert!(node.militia() > 0);
+        assert!(g.entities.characters[0].intelligence > 0);
+    }
+
+    #[test]
+    fn reject_invalid_budgets() {
+        let (mut g, node_idx) = game_with_city_cash(0.);
+        g.set_budget(node_idx, Institution::Health, 0.6).unwrap();
+        assert_eq!(
+            g.set_budget(node_idx, Institution::Law, 1.5),
+            Err(GameError::InvalidBudgetRate(1.5))
+        );
+        assert!(matches!(
+            g.set_budget(node_idx, Institution::Law, 0.5),
+            Err(GameError::BudgetExceeded { total, .. }) if total > 1.
+        ));
+        // replacing a budget only counts the new rate
+        g.set_budget(node_idx, Institution::Health, 0.9).unwrap();
+    }
+}
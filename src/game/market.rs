@@ -0,0 +1,265 @@
+use crate::game::building::BuildingKind;
+use crate::game::economy::REFERENCE_STOCK;
+use crate::game::game::CITY_RADIUS;
+use crate::game::BuildingId;
+use crate::game::Buildings;
+use crate::game::Commodity;
+use crate::game::Game;
+use crate::game::GameError;
+use crate::game::Market;
+use crate::game::Nation;
+use crate::game::Node;
+use std::collections::HashSet;
+
+/*
+Every city has a Market at its center, built along with the city. Every tick, the market
+- collects a fee on the value of everything taken from the node's market, which the city pays to
+  the nation that owns it, as far as the city's cash allows
+- stores surpluses in its own storage, and puts them back in the node's market when the stock
+  can't cover another tick of demand
+*/
+
+/// share of the value taken from a node's market that its Market building collects
+pub const MARKET_FEE: f32 = 0.05;
+
+/// most units of each commodity that a Market building keeps in reserve
+pub const MARKET_CAPACITY: u32 = 200;
+
+impl Market {
+    pub fn storage(&self, commodity: Commodity) -> u32 {
+        self.storage[commodity]
+    }
+
+    /// called after consumption and before the node closes its market; returns the fee
+    pub(crate) fn run(&mut self, node: &mut Node) -> f32 {
+        let mut fee = 0.;
+
+        for (commodity, reserve) in self.storage.iter_mut() {
+            let consumed = node.consumed[commodity];
+            fee += MARKET_FEE * consumed as f32 * node.price(commodity);
+
+            let stock = node.market[commodity];
+            let surplus_above = 2 * REFERENCE_STOCK + consumed;
+            if stock < consumed {
+                let released = (consumed - stock).min(*reserve);
+                node.market[commodity] += released;
+                *reserve -= released;
+            } else if stock > surplus_above {
+                let stored = (stock - surplus_above).min(MARKET_CAPACITY - *reserve);
+                node.market[commodity] -= stored;
+                *reserve += stored;
+            }
+        }
+
+        fee
+    }
+}
+
+impl Node {
+    /// run the node's Market building, if it still has one; returns the fee it collected
+    pub(crate) fn run_market(&mut self, buildings: &mut Buildings) -> f32 {
+        let market = self.buildings.iter().find_map(|building| match building {
+            BuildingId::Market(handle) => Some(*handle),
+            _ => None,
+        });
+
+        match market.and_then(|handle| buildings.markets.get_mut(handle)) {
+            Some(market) => market.run(self),
+            None => 0.,
+        }
+    }
+}
+
+impl Game {
+    /// the nation that owns a city
+    pub fn nation_of_node(&self, node_idx: usize) -> Option<usize> {
+        self.nations
+            .iter()
+            .position(|nation| nation.cities.contains(&node_idx))
+    }
+
+    /// found a city for a nation on an uncolonized node, starting with its Market
+    pub fn found_city(
+        &mut self,
+        nation_idx: usize,
+        node_idx: usize,
+    ) -> Result<BuildingId, GameError> {
+        self.check_node_idx(node_idx)?;
+        if nation_idx >= self.nations.len() {
+            return Err(GameError::InvalidNationIndex(nation_idx));
+        }
+        if self.is_colonized(node_idx) {
+            return Err(GameError::NodeAlreadyColonized(node_idx));
+        }
+
+        let market = self.place_market(node_idx)?;
+        self.nations[nation_idx].cities.insert(node_idx);
+        Ok(market)
+    }
+
+    /// found a new nation whose only city is the node
+    pub(crate) fn found_nation(&mut self, node_idx: usize) -> usize {
+        let mut cities = HashSet::new();
+        cities.insert(node_idx);
+        self.nations.push(Nation {
+            cities,
+            research: 0.,
+        });
+        self.nations.len() - 1
+    }
+
+    /// build a Market as close to the node's center as the free tiles allow
+    pub(crate) fn place_market(&mut self, node_idx: usize) -> Result<BuildingId, GameError> {
+        let kind = BuildingKind::Market;
+        let (width, height) = kind.spec().footprint;
+
+        // top left tile of a market centered on the node
+        let coor = self.nodes[node_idx].coor;
+        let [x, y] = [
+            coor.x as i64 - (width / 2) as i64,
+            coor.y as i64 - (height / 2) as i64,
+        ];
+
+        // rings of increasing distance around the center, row by row
+        for ring in 0..CITY_RADIUS as i64 {
+            for dy in -ring..=ring {
+                for dx in -ring..=ring {
+                    if dx.abs().max(dy.abs()) != ring || x + dx < 0 || y + dy < 0 {
+                        continue;
+                    }
+                    let top_left = [(x + dx) as u32, (y + dy) as u32];
+                    if self.check_build_site(top_left, width, height) == Ok(node_idx) {
+                        return Ok(self.insert_building(kind, top_left, node_idx));
+                    }
+                }
+            }
+        }
+
+        Err(GameError::NoBuildingSite { kind, node_idx })
+    }
+}
+
+#[cfg(test)]
+mod market_tests {
+    use super::*;
+    use crate::game::game::colonized_game;
+    use crate::game::money::{Account, Money, TransactionKind, STARTING_FUNDS};
+    use crate::GameOptions;
+
+    #[test]
+    fn colonization_without_a_market_site_changes_nothing() {
+        let mut g = GameOptions::default().set_seed(1234).build().unwrap();
+        // a building of another game stands in for the buildings on the tiles
+        let (other, node_idx) = colonized_game(100);
+        let market = *other.nodes[node_idx]
+            .buildings
+            .iter()
+            .find(|building| building.kind() == BuildingKind::Market)
+            .unwrap();
+
+        // every tile is taken around the nodes, but for the landing at their center
+        let radius = CITY_RADIUS as u32 + 1;
+        for node in &g.nodes {
+            let [x, y] = [node.coor.x as u32, node.coor.y as u32];
+            for tile_x in x.saturating_sub(radius)..=x + radius {
+                for tile_y in y.saturating_sub(radius)..=y + radius {
+                    let landing =
+                        tile_x + 1 >= x && tile_x <= x + 1 && tile_y + 1 >= y && tile_y <= y + 1;
+                    if !landing {
+                        g.building_grid.insert((tile_x, tile_y), market);
+                    }
+                }
+            }
+        }
+        let num_tiles = g.building_grid.len();
+
+        match g.colonize() {
+            Err(GameError::NoBuildingSite { kind, .. }) => assert_eq!(kind, BuildingKind::Market),
+            result => panic!("unexpected {:?}", result),
+        }
+        assert!((0..g.nodes.len()).all(|idx| !g.is_colonized(idx)));
+        assert!(g.nations.is_empty());
+        assert_eq!(g.building_grid.len(), num_tiles);
+        assert!(g.building_sites.is_empty());
+    }
+
+    #[test]
+    fn colonization_builds_a_market_next_to_the_landing() {
+        let (mut g, node_idx) = colonized_game(100);
+
+        let kinds: Vec<_> = g.nodes[node_idx]
+            .buildings
+            .iter()
+            .map(|building| building.kind())
+            .collect();
+        assert!(kinds.contains(&BuildingKind::Market));
+        assert_eq!(g.nation_of_node(node_idx), Some(0));
+
+        // the nation's next city gets its market right at the center
+        let other_idx = (0..g.nodes.len())
+            .find(|&idx| !g.is_colonized(idx))
+            .unwrap();
+        let market = g.found_city(0, other_idx).unwrap();
+        let coor = g.nodes[other_idx].coor;
+        assert_eq!(
+            g.building_grid.get(&(coor.x as u32, coor.y as u32)),
+            Some(&market)
+        );
+        assert_eq!(g.nations[0].cities.len(), 2);
+        assert_eq!(
+            g.found_city(0, other_idx),
+            Err(GameError::NodeAlreadyColonized(other_idx))
+        );
+        assert_eq!(
+            g.found_city(1, other_idx),
+            Err(GameError::InvalidNationIndex(1))
+        );
+    }
+
+    #[test]
+    fn markets_pay_fees_and_keep_reserves() {
+        let (mut g, node_idx) = colonized_game(100);
+        g.nodes[node_idx].market[Commodity::Fruit] = 1000;
+        let city = Account::City(node_idx);
+        let cash = Money::from_credits(1000.);
+        g.ledger
+            .transfer(0, TransactionKind::Grant, Account::World, city, cash);
+
+        g.update_state();
+        let treasury = g.ledger.balance(Account::Nation(0));
+        assert!(treasury > Money::from_credits(STARTING_FUNDS));
+        // the fee is taxed from the city
+        let tax = g
+            .ledger
+            .journal()
+            .find(|transaction| transaction.kind == TransactionKind::Tax)
+            .unwrap();
+        assert_eq!((tax.from, tax.to), (city, Account::Nation(0)));
+        assert_eq!(treasury, Money::from_credits(STARTING_FUNDS) + tax.amount);
+
+        // everything above twice the reference stock, plus what was eaten, goes to the reserve
+        let node = &g.nodes[node_idx];
+        let eaten = node.demand[Commodity::Fruit];
+        assert_eq!(
+            node.market[Commodity::Fruit],
+            1000 - eaten - MARKET_CAPACITY
+        );
+    }
+
+    #[test]
+    fn cities_without_income_pay_no_fees() {
+        let (mut g, node_idx) = colonized_game(100);
+        g.nodes[node_idx].market[Commodity::Fruit] = 10_000;
+
+        // the people eat every tick, but the city has no industries paying wages
+        for _ in 0..100 {
+            g.update_state();
+            assert!(g.ledger.balance(Account::City(node_idx)) >= Money::ZERO);
+        }
+        assert_eq!(
+            g.ledger.balance(Account::Nation(0)),
+            Money::from_credits(STARTING_FUNDS)
+        );
+        assert_eq!(g.ledger.audit(), Ok(()));
+    }
+}
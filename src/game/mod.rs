@@ -1,11 +1,41 @@
+pub mod arena;
+pub mod bonus;
+pub mod building;
+pub mod collision;
+pub mod corporation;
+pub mod economy;
+pub mod error;
+#[allow(clippy::module_inception)]
 pub mod game;
+pub mod housing;
+pub mod institution;
+pub mod market;
+pub mod money;
+pub mod order;
+pub mod population;
+pub mod recipe;
+pub mod rng;
+pub mod road;
+pub mod route;
+pub mod save;
+pub mod steering;
 pub mod update;
-
+pub mod warehouse;
+
+use crate::game::arena::{Arena, Handle};
+use crate::game::bonus::Bonuses;
+pub use crate::game::error::GameError;
+use crate::game::money::Ledger;
+use crate::game::order::Orders;
+use crate::game::recipe::Recipes;
+use crate::game::rng::GameRng;
+use crate::game::road::Roads;
+use crate::game::save::{serialize_ordered_map, serialize_ordered_set};
+use crate::game::warehouse::Trade;
 use crate::geometry::rectangle::Rectangle;
 use enum_map::EnumMap;
 use nalgebra::Point2;
-use rand_hc::Hc128Rng;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 pub trait MovableUnit {
     fn cal_max_steering_angle(&self) -> f32;
@@ -13,65 +43,94 @@ pub trait MovableUnit {
     // fn cal_next_coor(&self) -> NextCoor;
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub enum BuildingId {
-    Warehouse(usize), // can have as many as corps can affort; regular maintenance
+    Warehouse(Handle), // can have as many as corps can affort; regular maintenance
 
     /*
     - automatically built upon colonization, at the center of the city
     - Patrician-style market; act as money generator
     */
-    Market(usize),
+    Market(Handle),
 
     /*
-    - replaces market for the first colony
+    - replaces the market at the center, for the first colony only; that colony's market is
+      built next to it instead
     - provide basic food, production, power generation
     */
-    ColonyShipLanding(usize),
+    ColonyShipLanding(Handle),
 
-    AssemblyHall(usize), // one per city; unlock government options and private sector
+    AssemblyHall(Handle), // one per city; unlock government options and private sector
 
-    CorpHQ(usize), // at most one per corporation
+    CorpHQ(Handle), // at most one per corporation
 
-    Farm(usize),       // produce crops, high economy of scale
-    Mine(usize),       // produce metal
-    Workshop(usize),   // high yield, low economy of scale
-    Factory(usize),    // low yield, high economy of scale
-    House(usize),      // low cost, low vacancy
-    Skyscraper(usize), // high cost, high vacancy
+    Farm(Handle),       // produce crops, high economy of scale
+    Mine(Handle),       // produce metal
+    Workshop(Handle),   // high yield, low economy of scale
+    Factory(Handle),    // low yield, high economy of scale
+    House(Handle),      // low cost, low vacancy
+    Skyscraper(Handle), // high cost, high vacancy
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct Warehouse {
     storage: EnumMap<Commodity, u32>,
     demand_qtys: EnumMap<Commodity, u32>,
     import_prices: EnumMap<Commodity, f32>,
     sale_prices: EnumMap<Commodity, f32>,
+    ledger: VecDeque<Trade>,
 }
 
+#[derive(Default, Serialize, Deserialize)]
 pub struct Market {
     storage: EnumMap<Commodity, u32>,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Farm {
     output: u32,
 }
 
+/// a mine, workshop or factory, running one recipe of the game's registry
+#[derive(Default, Serialize, Deserialize)]
+pub struct Industry {
+    recipe: Option<String>, // idle until a recipe is assigned
+    progress: f32,          // fraction of an extra run, earned by bonuses
+}
+
+/// a house or a skyscraper
+#[derive(Default, Serialize, Deserialize)]
 pub struct Household {
     num_people: u32,
 }
 
-pub struct ColonyShipLanding {}
+#[derive(Default, Serialize, Deserialize)]
+pub struct ColonyShipLanding {
+    quarters: Household, // where the first colonists live
+}
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
+pub struct AssemblyHall {}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct CorpHQ {}
+
+#[derive(Default, Serialize, Deserialize)]
 pub struct Buildings {
-    farms: Vec<Farm>,
-    warehouses: Vec<Warehouse>,
-    households: Vec<Household>,
-    landings: Vec<ColonyShipLanding>,
+    farms: Arena<Farm>,
+    mines: Arena<Industry>,
+    workshops: Arena<Industry>,
+    factories: Arena<Industry>,
+    warehouses: Arena<Warehouse>,
+    markets: Arena<Market>,
+    houses: Arena<Household>,
+    skyscrapers: Arena<Household>,
+    landings: Arena<ColonyShipLanding>,
+    assembly_halls: Arena<AssemblyHall>,
+    corp_hqs: Arena<CorpHQ>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum CharacterMobility {
     Parked {
         node_idx: usize,
@@ -91,8 +150,9 @@ pub enum CharacterMobility {
         // collision hits
         //  - coor represents the center of the unit
         //  - all units are rectangular
-        //  - collision is based on the point-in-rectangle test
-        body: Rectangle, // range search, collision detection(?)
+        //  - collision is based on the separating axis test between the rectangles
+        //  - units that overlap each other are pushed apart, see collision.rs
+        body: Rectangle, // range search, collision detection
         character_idx: usize,
         /*
         pub armor: u32,             // hp
@@ -101,8 +161,9 @@ pub enum CharacterMobility {
     },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Character {
+    #[serde(serialize_with = "serialize_ordered_set")]
     pub children: HashSet<usize>,
 
     pub strength: u32,
@@ -123,19 +184,26 @@ Plan:
 - share holders are people only
 - start a private corporation by building the first building
 */
-struct Corporation {
+#[derive(Serialize, Deserialize)]
+pub struct Corporation {
     influence: u32,
-    buildings: HashSet<usize>,
+    #[serde(serialize_with = "serialize_ordered_set")]
+    buildings: HashSet<BuildingId>,
+    hq: Option<BuildingId>,
     ceo: usize, // character id,
     num_issued_shares: u32,
+    #[serde(serialize_with = "serialize_ordered_map")]
     shareholders: HashMap<usize, u32>, // character id, # shares
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct Nation {
+    #[serde(serialize_with = "serialize_ordered_set")]
     pub cities: HashSet<usize>,
+    pub research: f32, // tech progress, see Nation::tech_level
 }
 
+#[allow(dead_code)]
 struct Factions {
     cities: Vec<Node>,
     nations: Vec<Nation>,
@@ -143,15 +211,15 @@ struct Factions {
 }
 
 trait Entity {
-    fn get_x(&self) -> f32;
-    fn get_y(&self) -> f32;
+    fn get_x(&self) -> Option<f32>;
+    fn get_y(&self) -> Option<f32>;
 }
 
 // create units - yes
 // fire projectiles
 // destr
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct Entities {
     // entities that are located on the map
     pub projectiles: Vec<Projectile>,
@@ -159,35 +227,55 @@ pub struct Entities {
     pub characters: Vec<Character>,
 }
 
-#[derive(Enum)]
+#[derive(Enum, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Institution {
-    Health,  // lower death rate
-    Welfare, // increase birth rate
-    Saftely,
-    Law,
-    Education,
-    Research,
-    Military,
+    Health,    // lower death rate
+    Welfare,   // increase birth rate
+    Safety,    // lower crime
+    Law,       // lower crime
+    Education, // smarter characters
+    Research,  // tech progress of the nation
+    Military,  // militia
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct InstitutionData {
-    capacity: usize,    // effect capacity that the institution can provide
-    actual: usize,      // actual cumulative value
-    budget_rate: usize, // [0,1]
+    capacity: usize,  // effect capacity that the institution can provide
+    actual: usize,    // actual cumulative value
+    budget_rate: f32, // [0,1], share of the city's cash spent every tick
 }
 
+/// how the people of a node are housed, see housing.rs
+#[derive(Clone, Copy, Default, Debug, Serialize, Deserialize)]
+pub struct Housing {
+    pub capacity: usize,    // residents of all households, without overcrowding
+    pub housed: usize,      // within the capacity of their household
+    pub overcrowded: usize, // beyond the capacity of their household
+    pub homeless: usize,
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Node {
     // default radius
     pub market: EnumMap<Commodity, u32>,
+    pub demand: EnumMap<Commodity, u32>, // taken from the market during the last tick
+    pub(crate) consumed: EnumMap<Commodity, u32>, // taken from the market during this tick
+    pub price_history: VecDeque<EnumMap<Commodity, f32>>, // closing prices, oldest first
     pub institutions: EnumMap<Institution, InstitutionData>,
     pub population: usize,
+    pub housing: Housing,
+    pub births: usize,                  // during the last tick
+    pub deaths: usize,                  // during the last tick
+    pub(crate) birth_carry: f32,        // fraction of a birth, carried over to the next tick
+    pub(crate) death_carry: f32,        // fraction of a death, carried over to the next tick
+    pub(crate) education_progress: f32, // fraction of an intelligence point, see institution.rs
     pub coor: Point2<f32>,
     pub resources: [Commodity; 4],
+    #[serde(serialize_with = "serialize_ordered_set")]
     pub buildings: HashSet<BuildingId>,
 }
 
-#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Hash, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub enum EntityId {
     Island(usize),
     Unit(usize),
@@ -204,6 +292,7 @@ pub enum EntityId {
 // when nodes run out of hit points, they are captured by attackers
 // strategic
 
+#[allow(dead_code)]
 #[derive(Hash, Eq, PartialEq)]
 enum Promotion {
     Striker,
@@ -212,6 +301,7 @@ enum Promotion {
     NumPromotions, // c-style count of the enum, not part of actual data
 }
 
+#[allow(dead_code)]
 struct UnitStats {
     manpower: usize,
     attack: usize,
@@ -220,26 +310,43 @@ struct UnitStats {
     payload: usize,
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Game {
-    pub rng: Hc128Rng,
+    pub rng: GameRng,
+
+    pub tick: u64, // number of updates so far
+
+    pub ledger: Ledger,
+
+    pub recipes: Recipes,
+
+    pub bonuses: Bonuses,
 
     pub entities: Entities,
 
     pub nations: Vec<Nation>,
 
-    pub unit_destinations: HashMap<usize, usize>, // character idx -> city index
+    pub corporations: Vec<Corporation>,
+
+    #[serde(serialize_with = "serialize_ordered_map")]
+    pub unit_orders: HashMap<usize, Orders>, // character idx -> orders left to carry out
 
+    #[serde(serialize_with = "serialize_ordered_map")]
     pub building_grid: HashMap<(u32, u32), BuildingId>,
+    #[serde(serialize_with = "serialize_ordered_map")]
+    pub building_sites: HashMap<BuildingId, [u32; 2]>, // building -> top left tile
 
     // nodes
     pub nodes: Vec<Node>,
+    pub roads: Roads,
+    #[serde(serialize_with = "serialize_ordered_map")]
     pub unit_nodes: HashMap<usize, usize>, // map unit idx to node idx
 
     pub unit_x_axis: Vec<usize>,
     pub unit_y_axis: Vec<usize>,
 }
 
-#[derive(Enum, Clone, Copy)]
+#[derive(Enum, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Commodity {
     // primary resources
     Sand,
@@ -297,6 +404,7 @@ pub enum Gun {
     EnergyBeam,
 }
 
+#[derive(Serialize, Deserialize)]
 pub enum Projectile {
     Bullet {
         coor: Point2<f32>,
@@ -0,0 +1,368 @@
+use crate::game::BuildingId;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+/*
+Double-entry bookkeeping: money only ever moves from one account to another, so the balances of
+all accounts, the World included, always add up to 0. The World stands for everything outside the
+game: money paid to it (construction, upkeep) leaves the economy, money paid by it (grants, savings)
+enters it.
+
+Every transfer is written to a journal. The journal only keeps the latest transactions; older ones
+are folded into opening balances, so that an audit can still replay the journal from there and
+compare the result with the balances.
+*/
+
+/// number of transactions kept by the journal
+pub const JOURNAL_LENGTH: usize = 10_000;
+
+/// credits granted by the World to a new nation
+pub const STARTING_FUNDS: f32 = 10_000.;
+
+/// an amount of credits, counted in hundredths so that sums are exact
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    /// rounded to the nearest hundredth
+    pub fn from_credits(credits: f32) -> Self {
+        Money((f64::from(credits) * 100.).round() as i64)
+    }
+
+    pub fn credits(self) -> f32 {
+        self.0 as f32 / 100.
+    }
+
+    /// the fraction part / whole of the amount, rounded towards 0
+    pub fn share(self, part: u32, whole: u32) -> Money {
+        Money(self.0 * i64::from(part) / i64::from(whole))
+    }
+}
+
+/// "-12.05"
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let hundredths = self.0.abs();
+        let text = format!("{}{}.{:02}", sign, hundredths / 100, hundredths % 100);
+        f.pad(&text)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+/// anyone who can hold money
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum Account {
+    World,
+    Nation(usize),
+    City(usize), // the people of a node
+    Corporation(usize),
+    Character(usize),
+    Building(BuildingId),
+}
+
+impl Account {
+    /// parse the printed form of an account, e.g. Nation(0), City(3) or Warehouse(0)
+    pub fn from_name(name: &str) -> Option<Account> {
+        if name == "World" {
+            return Some(Account::World);
+        }
+        if let Some(building) = BuildingId::from_name(name) {
+            return Some(Account::Building(building));
+        }
+
+        let open = name.find('(')?;
+        let idx = name[open + 1..].strip_suffix(')')?.parse().ok()?;
+        match &name[..open] {
+            "Nation" => Some(Account::Nation(idx)),
+            "City" => Some(Account::City(idx)),
+            "Corporation" => Some(Account::Corporation(idx)),
+            "Character" => Some(Account::Character(idx)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            Account::World => "World".to_owned(),
+            Account::Nation(idx) => format!("Nation({})", idx),
+            Account::City(idx) => format!("City({})", idx),
+            Account::Corporation(idx) => format!("Corporation({})", idx),
+            Account::Character(idx) => format!("Character({})", idx),
+            Account::Building(building) => format!("{:?}", building),
+        };
+        f.pad(&text)
+    }
+}
+
+/// what a transfer pays for
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TransactionKind {
+    Grant,        // starting funds of a nation
+    Construction, // cost of a building
+    Tax,          // a city's market fees, paid to its nation
+    Wages,        // paid to the people working in industries
+    Purchase,     // a warehouse buying from its node's market
+    Sale,         // a business selling to its node's market
+    Upkeep,       // regular maintenance of a building
+    Investment,   // a founder's capital in a new corporation
+    Profit,       // what a business made since the last dividends, or covering its loss
+    Dividend,     // paid by a corporation to its shareholders
+    Budget,       // spent by a city on its institutions
+    Theft,        // stolen from a city by criminals
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub tick: u64,
+    pub kind: TransactionKind,
+    pub from: Account, // pays
+    pub to: Account,   // gets paid
+    pub amount: Money,
+}
+
+/// an inconsistency found by an audit
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuditError {
+    Unbalanced(Money), // sum of all balances
+    Mismatch {
+        account: Account,
+        balance: Money,
+        replayed: Money, // opening balance plus the journal
+    },
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuditError::Unbalanced(total) => {
+                write!(f, "balances add up to {} instead of 0", total)
+            }
+            AuditError::Mismatch {
+                account,
+                balance,
+                replayed,
+            } => write!(
+                f,
+                "{} has a balance of {} but its transactions add up to {}",
+                account, balance, replayed
+            ),
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Ledger {
+    balances: BTreeMap<Account, Money>,
+    opening: BTreeMap<Account, Money>, // balances before the oldest transaction of the journal
+    journal: VecDeque<Transaction>,
+}
+
+impl Ledger {
+    /// balances may go negative; callers check the funds where debt isn't allowed
+    pub fn transfer(
+        &mut self,
+        tick: u64,
+        kind: TransactionKind,
+        from: Account,
+        to: Account,
+        amount: Money,
+    ) {
+        if amount == Money::ZERO {
+            return;
+        }
+
+        *self.balances.entry(from).or_default() -= amount;
+        *self.balances.entry(to).or_default() += amount;
+
+        if self.journal.len() == JOURNAL_LENGTH {
+            let oldest = self.journal.pop_front().unwrap();
+            *self.opening.entry(oldest.from).or_default() -= oldest.amount;
+            *self.opening.entry(oldest.to).or_default() += oldest.amount;
+        }
+        self.journal.push_back(Transaction {
+            tick,
+            kind,
+            from,
+            to,
+            amount,
+        });
+    }
+
+    pub fn balance(&self, account: Account) -> Money {
+        self.balances.get(&account).cloned().unwrap_or_default()
+    }
+
+    /// every account that has ever held money, in order
+    pub fn balances(&self) -> impl Iterator<Item = (Account, Money)> + '_ {
+        self.balances
+            .iter()
+            .map(|(&account, &balance)| (account, balance))
+    }
+
+    /// latest transactions, oldest first
+    pub fn journal(&self) -> impl Iterator<Item = &Transaction> {
+        self.journal.iter()
+    }
+
+    /// check that the balances add up to 0, and that replaying the journal gives every balance
+    pub fn audit(&self) -> Result<(), AuditError> {
+        let total: Money = self.balances.values().cloned().sum();
+        if total != Money::ZERO {
+            return Err(AuditError::Unbalanced(total));
+        }
+
+        let mut replayed = self.opening.clone();
+        for transaction in &self.journal {
+            *replayed.entry(transaction.from).or_default() -= transaction.amount;
+            *replayed.entry(transaction.to).or_default() += transaction.amount;
+        }
+
+        for (&account, &balance) in &self.balances {
+            let replayed = replayed.get(&account).cloned().unwrap_or_default();
+            if replayed != balance {
+                return Err(AuditError::Mismatch {
+                    account,
+                    balance,
+                    replayed,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod money_tests {
+    use super::*;
+    use crate::game::arena::Handle;
+    use crate::game::game::colonized_game;
+
+    #[test]
+    fn money_display() {
+        assert_eq!(Money::from_credits(12.345).to_string(), "12.35");
+        assert_eq!(Money::from_credits(-0.05).to_string(), "-0.05");
+        assert_eq!(format!("{:>6}", Money::from_credits(3.)), "  3.00");
+    }
+
+    #[test]
+    fn account_names_round_trip() {
+        let accounts = [
+            Account::World,
+            Account::Nation(0),
+            Account::City(3),
+            Account::Corporation(1),
+            Account::Character(2),
+            Account::Building(BuildingId::Warehouse(Handle::new(0, 1))),
+        ];
+        for &account in &accounts {
+            assert_eq!(Account::from_name(&account.to_string()), Some(account));
+        }
+        assert_eq!(Account::from_name("Town(1)"), None);
+    }
+
+    #[test]
+    fn journal_replays_into_balances() {
+        let mut ledger = Ledger::default();
+        let nation = Account::Nation(0);
+        let city = Account::City(0);
+        let amount = Money::from_credits(10.);
+
+        ledger.transfer(0, TransactionKind::Grant, Account::World, nation, amount);
+        for tick in 1..=JOURNAL_LENGTH as u64 + 5 {
+            ledger.transfer(tick, TransactionKind::Wages, nation, city, Money(1));
+        }
+
+        assert_eq!(ledger.journal().count(), JOURNAL_LENGTH);
+        assert_eq!(ledger.balance(city), Money(JOURNAL_LENGTH as i64 + 5));
+        assert_eq!(ledger.audit(), Ok(()));
+
+        // a balance changed behind the journal's back
+        *ledger.balances.get_mut(&city).unwrap() += Money(1);
+        assert_eq!(ledger.audit(), Err(AuditError::Unbalanced(Money(1))));
+        *ledger.balances.get_mut(&nation).unwrap() -= Money(1);
+        match ledger.audit() {
+            Err(AuditError::Mismatch { account, .. }) => assert_eq!(account, nation),
+            result => panic!("unexpected {:?}", result),
+        }
+    }
+
+    #[test]
+    fn nations_pay_for_their_buildings() {
+        let (mut g, node_idx) = colonized_game(100);
+        let coor = g.nodes[node_idx].coor;
+        let [x, y] = [coor.x as u32, coor.y as u32];
+        let nation = Account::Nation(0);
+
+        g.create_farm([x + 3, y]).unwrap();
+        assert_eq!(
+            g.ledger.balance(nation),
+            Money::from_credits(STARTING_FUNDS - 200.)
+        );
+
+        // spend everything but 100 credits
+        g.ledger.transfer(
+            0,
+            TransactionKind::Upkeep,
+            nation,
+            Account::World,
+            g.ledger.balance(nation) - Money::from_credits(100.),
+        );
+        assert_eq!(
+            g.create_farm([x + 3, y + 2]),
+            Err(crate::GameError::InsufficientFunds {
+                account: nation,
+                cost: Money::from_credits(200.),
+                funds: Money::from_credits(100.),
+            })
+        );
+        assert_eq!(g.ledger.audit(), Ok(()));
+    }
+}
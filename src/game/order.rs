@@ -0,0 +1,345 @@
+use crate::game::route::Route;
+use crate::game::steering::Steering;
+use crate::game::CharacterMobility;
+use crate::game::Game;
+use crate::game::GameError;
+use crate::game::MovableUnit;
+use crate::game::Node;
+use crate::geometry::rectangle::Rectangle;
+use nalgebra::Point2;
+use std::collections::VecDeque;
+use std::fmt;
+
+/*
+Units carry out their orders one after the other:
+- an order to a node follows the roads (see route.rs); its route is planned when the order comes
+  first in the queue, from wherever the unit is then; if no road leads there, the unit stops and
+  keeps its orders, and its route reports why
+- an order to a point heads straight there, off the roads
+
+A unit whose last order takes it to a node parks there; otherwise it holds its position once the
+queue is empty. On patrol, every order that's carried out goes back to the end of the queue, so the
+unit loops through its orders forever, without parking. A hold order drops the whole queue.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Order {
+    Node(usize),
+    Point(Point2<f32>),
+}
+
+/// orders that a unit still has to carry out, the current one first
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Orders {
+    pub queue: VecDeque<Order>,
+    pub patrol: bool,
+    pub route: Option<Route>, // of the current order, once planned, if it's to a node
+    pub unreachable_from: Option<usize>, // nearest node, if the current order is out of reach
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Order::Node(node_idx) => write!(f, "node {}", node_idx),
+            Order::Point(point) => write!(f, "({:.2},{:.2})", point.x, point.y),
+        }
+    }
+}
+
+impl Orders {
+    /// steer a unit's body on its current order; returns the node to park at, once the unit is
+    /// done with its last order and it's to a node
+    pub(crate) fn advance(
+        &mut self,
+        body: &mut Rectangle,
+        steering: &mut Steering,
+        nodes: &[Node],
+    ) -> Option<usize> {
+        let done = match self.queue.front() {
+            Some(&Order::Point(point)) => steering.steer(body, point),
+            Some(Order::Node(_)) => match &mut self.route {
+                Some(route) => route.advance(body, steering, nodes),
+                None => false, // not planned yet
+            },
+            None => return None,
+        };
+        if !done {
+            return None;
+        }
+
+        self.route = None;
+        let order = self.queue.pop_front()?;
+        if self.patrol {
+            self.queue.push_back(order);
+        }
+        match order {
+            Order::Node(node_idx) if self.queue.is_empty() => Some(node_idx),
+            _ => None,
+        }
+    }
+}
+
+impl Game {
+    /// give a deployed unit an order, either instead of its current ones or after them
+    pub fn order_unit(
+        &mut self,
+        character_idx: usize,
+        order: Order,
+        queued: bool,
+    ) -> Result<(), GameError> {
+        let coor = self.get_unit_coor(character_idx)?;
+        if let Order::Node(node_idx) = order {
+            self.check_node_idx(node_idx)?;
+        }
+
+        if queued {
+            if let Some(orders) = self.unit_orders.get_mut(&character_idx) {
+                orders.queue.push_back(order);
+                return Ok(());
+            }
+        }
+
+        // the order comes first: plan it right away, so that an unreachable node is reported
+        let route = match order {
+            Order::Node(node_idx) => Some(self.route_from(coor, node_idx)?),
+            Order::Point(_) => None,
+        };
+        let orders = Orders {
+            queue: vec![order].into_iter().collect(),
+            patrol: false,
+            route,
+            unreachable_from: None,
+        };
+        self.unit_orders.insert(character_idx, orders);
+        Ok(())
+    }
+
+    /// send a unit to a node, along the roads, instead of its current orders
+    pub fn set_unit_destination(
+        &mut self,
+        character_idx: usize,
+        node_idx: usize,
+    ) -> Result<(), GameError> {
+        self.order_unit(character_idx, Order::Node(node_idx), false)
+    }
+
+    /// make a unit loop through its orders
+    pub fn patrol_unit(&mut self, character_idx: usize) -> Result<(), GameError> {
+        self.get_unit_coor(character_idx)?;
+        let orders = self
+            .unit_orders
+            .get_mut(&character_idx)
+            .ok_or(GameError::NoOrders(character_idx))?;
+        orders.patrol = true;
+        Ok(())
+    }
+
+    /// stop a unit where it is, dropping its orders
+    pub fn hold_unit(&mut self, character_idx: usize) -> Result<(), GameError> {
+        self.get_unit_coor(character_idx)?;
+        self.unit_orders.remove(&character_idx);
+        Ok(())
+    }
+
+    /// the route from the node closest to a point
+    fn route_from(&self, coor: Point2<f32>, node_idx: usize) -> Result<Route, GameError> {
+        let start = self.nearest_node(coor).expect("bug: the world has nodes");
+        let path = self.plan_route(start, node_idx)?;
+        Ok(Route {
+            waypoints: path.into_iter().collect(),
+        })
+    }
+
+    /// plan the routes of units whose current order is to a node; a unit that can't reach it
+    /// stays where it is, until it's given other orders
+    fn plan_routes(&mut self) {
+        let mut planned = Vec::new();
+        for (&character_idx, orders) in &self.unit_orders {
+            if orders.unreachable_from.is_some() {
+                continue; // the roads don't change, and the unit doesn't move
+            }
+            if let (Some(&Order::Node(node_idx)), None) = (orders.queue.front(), &orders.route) {
+                if let Ok(coor) = self.get_unit_coor(character_idx) {
+                    planned.push((character_idx, self.route_from(coor, node_idx)));
+                }
+            }
+        }
+
+        for (character_idx, route) in planned {
+            let orders = self.unit_orders.get_mut(&character_idx).unwrap();
+            match route {
+                Ok(route) => orders.route = Some(route),
+                Err(GameError::NoRoute { from, .. }) => orders.unreachable_from = Some(from),
+                Err(err) => unreachable!("bug: the node was checked with the order: {}", err),
+            }
+        }
+    }
+
+    /// move every unit with orders; units done with their orders park or hold their position
+    pub(crate) fn move_units(&mut self) {
+        self.plan_routes();
+
+        let characters = &mut self.entities.characters;
+        let nodes = &self.nodes;
+
+        self.unit_orders.retain(|&character_idx, orders| {
+            let character = &mut characters[character_idx];
+            let mut steering =
+                Steering::new(character.cal_speed(), character.cal_max_steering_angle());
+
+            let body = match &mut character.mobility {
+                CharacterMobility::Unit { body, .. } => body,
+                CharacterMobility::Parked { .. } => return false, // no longer a unit; drop the orders
+            };
+
+            if let Some(node_idx) = orders.advance(body, &mut steering, nodes) {
+                // arrival
+                character.mobility = CharacterMobility::Parked { node_idx };
+                return false;
+            }
+
+            // if enemy units are within range (say, r) of the longest weapon, fire at them
+            // 1. search for units within "r", filter by hostility; called this collection of units "C"
+            // 2. turn "C" into a binary heap, sorted by distance in ascending order, called "H"
+            // 2.1. for each weapon, if top of "H" is in range
+            // 2.1.1 if weapon doesn't have a target, assign the target
+            // 2.1.2 if weapon has a target but the new target has lower HP, assign the new target
+            // 2.2 repeat until all weapon have a target or no more target in range
+            // 2.3 fire all weapons
+
+            !orders.queue.is_empty()
+        });
+    }
+}
+
+#[cfg(test)]
+mod order_tests {
+    use super::*;
+    use crate::game::game::colonized_game;
+
+    fn deployed_unit() -> Game {
+        let (mut g, _) = colonized_game(100);
+        g.deploy_character(0).unwrap();
+        g
+    }
+
+    #[test]
+    fn queued_orders_run_in_sequence() {
+        let mut g = deployed_unit();
+        let start = g.get_unit_coor(0).unwrap();
+        // straight ahead of a unit that was just deployed
+        let point = start + nalgebra::Vector2::new(0., 30.);
+
+        g.order_unit(0, Order::Point(point), false).unwrap();
+        g.order_unit(0, Order::Point(start), true).unwrap();
+        assert_eq!(g.unit_orders[&0].queue.len(), 2);
+
+        // 30 at speed 10: 3 ticks to get there
+        for _ in 0..3 {
+            g.update_state();
+        }
+        assert_eq!(g.get_unit_coor(0).unwrap(), point);
+        assert_eq!(g.unit_orders[&0].queue.len(), 1);
+
+        // it turns around, then goes back to the start, where it holds its position, still deployed
+        for _ in 0..20 {
+            g.update_state();
+        }
+        assert_eq!(g.get_unit_coor(0).unwrap(), start);
+        assert!(!g.unit_orders.contains_key(&0));
+    }
+
+    #[test]
+    fn patrols_loop_until_held() {
+        let mut g = deployed_unit();
+        let start = g.get_unit_coor(0).unwrap();
+        let point = start + nalgebra::Vector2::new(0., 20.);
+
+        assert_eq!(g.patrol_unit(0), Err(GameError::NoOrders(0)));
+        g.order_unit(0, Order::Point(point), false).unwrap();
+        g.order_unit(0, Order::Point(start), true).unwrap();
+        g.patrol_unit(0).unwrap();
+
+        // the unit keeps going back and forth, and every order it reaches goes back to the end of
+        // the queue
+        let mut visits = Vec::new();
+        for _ in 0..60 {
+            g.update_state();
+            let coor = g.get_unit_coor(0).unwrap();
+            if (coor == point || coor == start) && visits.last() != Some(&coor) {
+                visits.push(coor);
+                let other = if coor == point { start } else { point };
+                let queue: Vec<_> = g.unit_orders[&0].queue.iter().cloned().collect();
+                assert_eq!(queue, vec![Order::Point(other), Order::Point(coor)]);
+            }
+        }
+        assert!(visits.len() >= 4);
+        for (idx, visit) in visits.iter().enumerate() {
+            assert_eq!(*visit, if idx % 2 == 0 { point } else { start });
+        }
+
+        g.update_state();
+        g.hold_unit(0).unwrap();
+        let coor = g.get_unit_coor(0).unwrap();
+        g.update_state();
+        assert_eq!(g.get_unit_coor(0).unwrap(), coor);
+    }
+
+    #[test]
+    fn last_order_to_a_node_parks() {
+        let mut g = deployed_unit();
+        let start = g.get_unit_coor(0).unwrap();
+        let home = g.nearest_node(start).unwrap();
+        let away = (home + 1) % g.nodes.len();
+
+        g.order_unit(0, Order::Node(away), false).unwrap();
+        g.order_unit(0, Order::Node(home), true).unwrap();
+        // only the first order's route is planned right away; the second one waits for its turn
+        let orders = &g.unit_orders[&0];
+        assert_eq!(orders.queue.len(), 2);
+        let route = orders.route.as_ref().unwrap();
+        assert_eq!(route.waypoints.back(), Some(&away));
+
+        for _ in 0..1000 {
+            if let CharacterMobility::Parked { node_idx } = g.entities.characters[0].mobility {
+                assert_eq!(node_idx, home);
+                return;
+            }
+            g.update_state();
+        }
+        panic!("unit 0 should have come back home");
+    }
+
+    #[test]
+    fn unreachable_patrol_waypoints_are_kept() {
+        let mut g = deployed_unit();
+        let start = g.get_unit_coor(0).unwrap();
+        let point = start + nalgebra::Vector2::new(0., 20.);
+        let away = (g.nearest_node(start).unwrap() + 1) % g.nodes.len();
+
+        g.order_unit(0, Order::Point(point), false).unwrap();
+        g.order_unit(0, Order::Node(away), true).unwrap();
+        g.patrol_unit(0).unwrap();
+        g.roads.cut_off(away);
+
+        // the unit gets to the point, then stays there with all of its orders
+        for _ in 0..20 {
+            g.update_state();
+        }
+        assert_eq!(g.get_unit_coor(0).unwrap(), point);
+        let orders = &g.unit_orders[&0];
+        let queue: Vec<_> = orders.queue.iter().cloned().collect();
+        assert_eq!(queue, vec![Order::Node(away), Order::Point(point)]);
+        let from = g.nearest_node(point).unwrap();
+        assert_eq!(orders.unreachable_from, Some(from));
+        assert_eq!(
+            g.unit_route(0).err(),
+            Some(GameError::NoRoute { from, to: away })
+        );
+
+        // until it's given other orders
+        g.order_unit(0, Order::Point(start), false).unwrap();
+        assert_eq!(g.unit_orders[&0].unreachable_from, None);
+        assert_eq!(g.unit_route(0), Ok(None));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::game::Institution;
+use crate::game::Node;
+
+/*
+Demographics of colonized nodes, once per tick after civilians have eaten:
+- births: a base rate, raised by Welfare, lowered by poor housing, and only for the share of people
+  who were fed; no one is born once the households are full (see housing.rs)
+- deaths: a base rate, lowered by Health, plus starvation for the share of people who weren't
+
+Rates are fractions of the population per tick. Fractions of people are carried over to the next
+tick, so that small cities still grow and shrink.
+*/
+
+/// births per person and per tick, in a fed city without Welfare
+pub const BIRTH_RATE: f32 = 0.002;
+
+/// deaths per person and per tick, in a fed city without Health
+pub const DEATH_RATE: f32 = 0.001;
+
+/// extra deaths per person and per tick, in a city where nobody was fed
+pub const STARVATION_RATE: f32 = 0.01;
+
+/// Welfare can at most double the birth rate, and Health halve the death rate
+const WELFARE_MAX_BONUS: f32 = 1.;
+const HEALTH_MAX_BONUS: f32 = 0.5;
+
+impl Node {
+    /// births and deaths of a tick in which `unfed` units of food demand couldn't be met
+    pub(crate) fn update_population(&mut self, unfed: u32) {
+        let demand = self.food_demand();
+        let fed = if demand == 0 {
+            1.
+        } else {
+            1. - unfed as f32 / demand as f32
+        };
+
+        let welfare = self.institutions[Institution::Welfare].effect();
+        let health = self.institutions[Institution::Health].effect();
+        let satisfaction = self.housing.satisfaction();
+        let birth_rate = BIRTH_RATE * (1. + WELFARE_MAX_BONUS * welfare) * satisfaction * fed;
+        let death_rate =
+            DEATH_RATE * (1. - HEALTH_MAX_BONUS * health) + STARVATION_RATE * (1. - fed);
+
+        let population = self.population as f32;
+        let (births, birth_carry) = whole_people(population * birth_rate + self.birth_carry);
+        let (deaths, death_carry) = whole_people(population * death_rate + self.death_carry);
+
+        let room = self.housing.max_residents().saturating_sub(self.population);
+        self.births = births.min(room);
+        self.deaths = deaths.min(self.population + self.births);
+        self.birth_carry = birth_carry;
+        self.death_carry = death_carry;
+        self.population = self.population + self.births - self.deaths;
+    }
+}
+
+/// split a number of people into whole people and a fraction to carry over
+fn whole_people(people: f32) -> (usize, f32) {
+    let whole = people.floor();
+    (whole as usize, people - whole)
+}
+
+#[cfg(test)]
+mod population_tests {
+    use super::*;
+    use crate::game::Housing;
+    use crate::GameOptions;
+
+    fn node_after(ticks: usize, unfed: impl Fn(&Node) -> u32, setup: impl Fn(&mut Node)) -> Node {
+        let mut g = GameOptions::default()
+            .set_seed(1234)
+            .set_starting_population(1000)
+            .build()
+            .unwrap();
+        let mut node = g.nodes.swap_remove(0);
+        node.housing = Housing {
+            capacity: 2000,
+            housed: 1000,
+            ..Default::default()
+        };
+        setup(&mut node);
+        for _ in 0..ticks {
+            let unfed = unfed(&node);
+            node.update_population(unfed);
+        }
+        node
+    }
+
+    #[test]
+    fn fed_cities_grow() {
+        let node = node_after(10, |_| 0, |_| {});
+        // 2 births and 1 death per tick
+        assert_eq!(node.population, 1010);
+        assert_eq!((node.births, node.deaths), (2, 1));
+    }
+
+    #[test]
+    fn starving_cities_shrink() {
+        let node = node_after(10, |node| node.food_demand(), |_| {});
+        assert!(node.population < 1000 - 100);
+        assert_eq!(node.births, 0);
+    }
+
+    #[test]
+    fn institutions_change_the_rates() {
+        let institutions = |node: &mut Node| {
+            node.institutions[Institution::Welfare].actual = 100;
+            node.institutions[Institution::Health].actual = 100;
+        };
+        let node = node_after(10, |_| 0, institutions);
+        // 3 births and 0.75 deaths per tick
+        assert_eq!(node.population, 1000 + 30 - 7);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::game::Commodity;
+use serde::de::value::Error as ValueError;
+use serde::de::IntoDeserializer;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// the recipes compiled into the game; see the file for its format
+const BUILTIN_RECIPES: &str = include_str!("../../data/recipes.toml");
+
+/// buildings that transform commodities by running a recipe
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Workplace {
+    Mine,
+    Workshop,
+    Factory,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Recipe {
+    pub name: String,
+    pub workplace: Workplace,
+    pub labor: u32, // workers needed for one run
+    pub inputs: Vec<(Commodity, u32)>,
+    pub outputs: Vec<(Commodity, u32)>,
+}
+
+/// every known recipe, in the order of the data file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Recipes {
+    recipes: Vec<Recipe>,
+}
+
+#[derive(Debug)]
+pub enum RecipeError {
+    Io(io::Error),
+    Format(toml::de::Error),
+    InvalidName(String),
+    DuplicateRecipe(String),
+    UnknownCommodity { recipe: String, name: String },
+    NoOutputs(String),
+}
+
+impl fmt::Display for RecipeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecipeError::Io(err) => write!(f, "cannot read recipes: {}", err),
+            RecipeError::Format(err) => write!(f, "invalid recipes: {}", err),
+            RecipeError::InvalidName(name) => {
+                write!(f, "recipe name \"{}\" must be a single word", name)
+            }
+            RecipeError::DuplicateRecipe(name) => write!(f, "recipe {} is defined twice", name),
+            RecipeError::UnknownCommodity { recipe, name } => {
+                write!(f, "recipe {} uses unknown commodity {}", recipe, name)
+            }
+            RecipeError::NoOutputs(name) => write!(f, "recipe {} doesn't produce anything", name),
+        }
+    }
+}
+
+impl std::error::Error for RecipeError {}
+
+/// layout of the data file; commodities are named by their variants
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecipeFile {
+    recipe: Vec<RecipeEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecipeEntry {
+    name: String,
+    building: Workplace,
+    #[serde(default)]
+    labor: u32,
+    #[serde(default)]
+    inputs: BTreeMap<String, u32>,
+    #[serde(default)]
+    outputs: BTreeMap<String, u32>,
+}
+
+impl Commodity {
+    /// the commodity whose variant is named `name`
+    pub fn from_name(name: &str) -> Option<Commodity> {
+        let deserializer: serde::de::value::StrDeserializer<ValueError> = name.into_deserializer();
+        Commodity::deserialize(deserializer).ok()
+    }
+}
+
+impl Recipes {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RecipeError> {
+        let content = fs::read_to_string(path).map_err(RecipeError::Io)?;
+        Self::parse(&content)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, RecipeError> {
+        let file: RecipeFile = toml::from_str(content).map_err(RecipeError::Format)?;
+
+        let mut recipes: Vec<Recipe> = Vec::with_capacity(file.recipe.len());
+        for entry in file.recipe {
+            let name = entry.name;
+
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(RecipeError::InvalidName(name));
+            }
+            if recipes.iter().any(|recipe| recipe.name == name) {
+                return Err(RecipeError::DuplicateRecipe(name));
+            }
+
+            let commodities = |quantities: BTreeMap<String, u32>| {
+                quantities
+                    .into_iter()
+                    .filter(|&(_, qty)| qty > 0)
+                    .map(|(commodity, qty)| match Commodity::from_name(&commodity) {
+                        Some(commodity) => Ok((commodity, qty)),
+                        None => Err(RecipeError::UnknownCommodity {
+                            recipe: name.clone(),
+                            name: commodity,
+                        }),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            let inputs = commodities(entry.inputs)?;
+            let outputs = commodities(entry.outputs)?;
+
+            if outputs.is_empty() {
+                return Err(RecipeError::NoOutputs(name));
+            }
+
+            recipes.push(Recipe {
+                name,
+                workplace: entry.building,
+                labor: entry.labor,
+                inputs,
+                outputs,
+            });
+        }
+
+        Ok(Self { recipes })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Recipe> {
+        self.recipes.iter().find(|recipe| recipe.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Recipe> {
+        self.recipes.iter()
+    }
+}
+
+impl Default for Recipes {
+    fn default() -> Self {
+        Self::parse(BUILTIN_RECIPES).expect("bug: built-in recipes are invalid")
+    }
+}
+
+#[cfg(test)]
+mod recipe_tests {
+    use super::*;
+
+    #[test]
+    fn builtin_recipes() {
+        let recipes = Recipes::default();
+        let chocolate = recipes.get("chocolate").unwrap();
+        assert_eq!(chocolate.workplace, Workplace::Workshop);
+        assert_eq!(
+            chocolate.inputs,
+            vec![(Commodity::Cocoa, 1), (Commodity::Sugar, 1)]
+        );
+        assert_eq!(chocolate.outputs, vec![(Commodity::Chocolate, 1)]);
+    }
+
+    #[test]
+    fn reject_invalid_recipes() {
+        let parse = |content: &str| Recipes::parse(content).unwrap_err();
+
+        let entry = "[[recipe]]\nname = \"glass\"\nbuilding = \"Workshop\"\n";
+        match parse(&format!("{}outputs = {{ Glas = 1 }}", entry)) {
+            RecipeError::UnknownCommodity { name, .. } => assert_eq!(name, "Glas"),
+            err => panic!("unexpected {:?}", err),
+        }
+        match parse(entry) {
+            RecipeError::NoOutputs(_) => {}
+            err => panic!("unexpected {:?}", err),
+        }
+        let glass = format!("{}outputs = {{ Glass = 1 }}\n", entry);
+        match parse(&format!("{}{}", glass, glass)) {
+            RecipeError::DuplicateRecipe(_) => {}
+            err => panic!("unexpected {:?}", err),
+        }
+        match parse("[[recipe]]\nname = \"glass\"\nbuilding = \"Kitchen\"\n") {
+            RecipeError::Format(_) => {}
+            err => panic!("unexpected {:?}", err),
+        }
+    }
+}
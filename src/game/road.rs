@@ -0,0 +1,223 @@
+use crate::game::Game;
+use crate::game::GameError;
+use nalgebra::{distance, Point2};
+use spade::delaunay::{DelaunayWalkLocate, FloatDelaunayTriangulation};
+use std::collections::{BTreeSet, HashMap};
+
+/*
+Roads link nodes into a graph, generated along with the world:
+- the Delaunay triangulation of the nodes' centers connects every node to its natural neighbours,
+  without crossings
+- optionally, it's pruned to the relative neighbourhood graph: a road between a and b is dropped
+  if some node c is closer to both a and b than they are to each other, so that the network keeps
+  its short roads only, and stays connected
+*/
+
+/// a road between two nodes, from the lower index to the higher one
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Road {
+    pub from: usize,
+    pub to: usize,
+    pub length: f32,
+}
+
+/// the roads of the world, and the roads leaving each node
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Roads {
+    roads: Vec<Road>,         // sorted by (from, to)
+    by_node: Vec<Vec<usize>>, // indices into roads, sorted
+}
+
+impl Road {
+    /// the node at the other end of the road
+    pub fn other_end(&self, node_idx: usize) -> usize {
+        if self.from == node_idx {
+            self.to
+        } else {
+            self.from
+        }
+    }
+}
+
+impl Roads {
+    /// the road network between nodes centered on `coors`
+    pub fn generate(coors: &[Point2<f32>], prune: bool) -> Self {
+        let mut links = triangulate(coors);
+        if prune {
+            links = relative_neighbourhood(coors, &links);
+        }
+
+        let roads: Vec<_> = links
+            .into_iter()
+            .map(|(from, to)| Road {
+                from,
+                to,
+                length: distance(&coors[from], &coors[to]),
+            })
+            .collect();
+
+        let mut by_node = vec![Vec::new(); coors.len()];
+        for (road_idx, road) in roads.iter().enumerate() {
+            by_node[road.from].push(road_idx);
+            by_node[road.to].push(road_idx);
+        }
+
+        Self { roads, by_node }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Road> {
+        self.roads.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.roads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roads.is_empty()
+    }
+
+    /// roads leaving a node
+    pub fn of_node(&self, node_idx: usize) -> impl Iterator<Item = &Road> {
+        self.by_node[node_idx]
+            .iter()
+            .map(move |&road_idx| &self.roads[road_idx])
+    }
+
+    /// drop every road of a node, so that it can't be reached anymore
+    #[cfg(test)]
+    pub(crate) fn cut_off(&mut self, node_idx: usize) {
+        self.roads
+            .retain(|road| road.from != node_idx && road.to != node_idx);
+        for by_node in &mut self.by_node {
+            by_node.clear();
+        }
+        for (road_idx, road) in self.roads.iter().enumerate() {
+            self.by_node[road.from].push(road_idx);
+            self.by_node[road.to].push(road_idx);
+        }
+    }
+}
+
+/// pairs of nodes linked by the Delaunay triangulation, lower index first, in order
+fn triangulate(coors: &[Point2<f32>]) -> BTreeSet<(usize, usize)> {
+    let mut triangulation: FloatDelaunayTriangulation<[f64; 2], DelaunayWalkLocate> =
+        FloatDelaunayTriangulation::with_walk_locate();
+
+    let mut node_of_vertex = HashMap::new();
+    for (node_idx, coor) in coors.iter().enumerate() {
+        let vertex = triangulation.insert([f64::from(coor.x), f64::from(coor.y)]);
+        node_of_vertex.insert(vertex, node_idx);
+    }
+
+    triangulation
+        .edges()
+        .map(|edge| {
+            let a = node_of_vertex[&edge.from().fix()];
+            let b = node_of_vertex[&edge.to().fix()];
+            (a.min(b), a.max(b))
+        })
+        .collect()
+}
+
+/// the links without a node closer to both of their ends than they are to each other
+fn relative_neighbourhood(
+    coors: &[Point2<f32>],
+    links: &BTreeSet<(usize, usize)>,
+) -> BTreeSet<(usize, usize)> {
+    links
+        .iter()
+        .cloned()
+        .filter(|&(a, b)| {
+            let length = distance(&coors[a], &coors[b]);
+            !coors.iter().enumerate().any(|(c, coor)| {
+                c != a
+                    && c != b
+                    && distance(&coors[a], coor).max(distance(&coors[b], coor)) < length
+            })
+        })
+        .collect()
+}
+
+impl Game {
+    /// nodes linked to a node by a road, in order
+    pub fn neighbouring_nodes(&self, node_idx: usize) -> Result<Vec<usize>, GameError> {
+        self.check_node_idx(node_idx)?;
+        let mut neighbours: Vec<_> = self
+            .roads
+            .of_node(node_idx)
+            .map(|road| road.other_end(node_idx))
+            .collect();
+        neighbours.sort_unstable();
+        Ok(neighbours)
+    }
+}
+
+#[cfg(test)]
+mod road_tests {
+    use super::*;
+    use crate::GameOptions;
+
+    fn square_with_center() -> Vec<Point2<f32>> {
+        vec![
+            Point2::new(0., 0.),
+            Point2::new(10., 0.),
+            Point2::new(10., 10.),
+            Point2::new(0., 10.),
+            Point2::new(5., 5.),
+        ]
+    }
+
+    #[test]
+    fn triangulation_links_natural_neighbours() {
+        let roads = Roads::generate(&square_with_center(), false);
+        let links: Vec<_> = roads.iter().map(|road| (road.from, road.to)).collect();
+        // the sides of the square, and a spoke to the center from every corner
+        assert_eq!(
+            links,
+            vec![
+                (0, 1),
+                (0, 3),
+                (0, 4),
+                (1, 2),
+                (1, 4),
+                (2, 3),
+                (2, 4),
+                (3, 4)
+            ]
+        );
+        assert_eq!(roads.of_node(4).count(), 4);
+        assert_eq!(roads.iter().next().unwrap().length, 10.);
+    }
+
+    #[test]
+    fn pruning_keeps_short_roads() {
+        let roads = Roads::generate(&square_with_center(), true);
+        let links: Vec<_> = roads.iter().map(|road| (road.from, road.to)).collect();
+        // the center is closer to both ends of every side
+        assert_eq!(links, vec![(0, 4), (1, 4), (2, 4), (3, 4)]);
+    }
+
+    #[test]
+    fn every_node_is_reachable() {
+        let g = GameOptions::default()
+            .set_seed(1234)
+            .set_pruned_roads(true)
+            .build()
+            .unwrap();
+
+        let mut reached = vec![false; g.nodes.len()];
+        let mut stack = vec![0];
+        while let Some(node_idx) = stack.pop() {
+            if !reached[node_idx] {
+                reached[node_idx] = true;
+                stack.extend(g.neighbouring_nodes(node_idx).unwrap());
+            }
+        }
+        assert!(reached.iter().all(|&reached| reached));
+        assert_eq!(
+            g.neighbouring_nodes(g.nodes.len()),
+            Err(GameError::InvalidNodeIndex(g.nodes.len()))
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use crate::algorithm::shortest_path::a_star;
+use crate::game::order::Order;
+use crate::game::steering::Steering;
+use crate::game::Game;
+use crate::game::GameError;
+use crate::game::MovableUnit;
+use crate::game::Node;
+use crate::geometry::rectangle::Rectangle;
+use nalgebra::{distance, Point2};
+use std::collections::VecDeque;
+
+/*
+Units travel between nodes along the roads. An order to a node is planned once, with A* over the road
+network (the cost of a road is its length, and the straight line to the goal is the heuristic):
+- the unit first heads to the node closest to it, unless it's already there
+- then it passes through every node of the path, up to the destination, where it parks
+
+Every tick, a unit steers toward its next waypoint (see steering.rs), and goes on to the following
+ones with whatever movement it has left once it reaches one.
+*/
+
+/// nodes that a unit still has to pass through, the destination last
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Route {
+    pub waypoints: VecDeque<usize>,
+}
+
+impl Route {
+    pub fn destination(&self) -> Option<usize> {
+        self.waypoints.back().cloned()
+    }
+
+    /// distance left to travel from `coor`
+    pub fn remaining_distance(&self, coor: Point2<f32>, nodes: &[Node]) -> f32 {
+        let mut total = 0.;
+        let mut from = coor;
+        for &node_idx in &self.waypoints {
+            let to = nodes[node_idx].coor;
+            total += distance(&from, &to);
+            from = to;
+        }
+        total
+    }
+
+    /// steer a unit's body along the route; returns true once it has no waypoint left
+    pub(crate) fn advance(
+        &mut self,
+        body: &mut Rectangle,
+        steering: &mut Steering,
+        nodes: &[Node],
+    ) -> bool {
+        while let Some(&node_idx) = self.waypoints.front() {
+            if !steering.steer(body, nodes[node_idx].coor) {
+                return false;
+            }
+            self.waypoints.pop_front();
+        }
+        true
+    }
+}
+
+impl Game {
+    /// the cheapest path along the roads, both ends included
+    pub fn plan_route(&self, from: usize, to: usize) -> Result<Vec<usize>, GameError> {
+        self.check_node_idx(from)?;
+        self.check_node_idx(to)?;
+
+        let goal = self.nodes[to].coor;
+        let edges = |node_idx| {
+            self.roads
+                .of_node(node_idx)
+                .map(|road| (road.other_end(node_idx), road.length))
+                .collect()
+        };
+        let heuristic = |node_idx: usize| distance(&self.nodes[node_idx].coor, &goal);
+
+        a_star(self.nodes.len(), from, to, edges, heuristic)
+            .map(|(path, _)| path)
+            .ok_or(GameError::NoRoute { from, to })
+    }
+
+    /// the node closest to a point
+    pub fn nearest_node(&self, coor: Point2<f32>) -> Option<usize> {
+        (0..self.nodes.len()).min_by(|&a, &b| {
+            let a = distance(&self.nodes[a].coor, &coor);
+            let b = distance(&self.nodes[b].coor, &coor);
+            a.partial_cmp(&b).unwrap()
+        })
+    }
+
+    /// the planned route of a unit, and about the number of ticks until its arrival, turns aside;
+    /// fails if no road leads to the node of its current order
+    pub fn unit_route(&self, character_idx: usize) -> Result<Option<(&Route, u64)>, GameError> {
+        let coor = self.get_unit_coor(character_idx)?;
+        let orders = self.unit_orders.get(&character_idx);
+        if let Some(orders) = orders {
+            if let (Some(from), Some(&Order::Node(to))) =
+                (orders.unreachable_from, orders.queue.front())
+            {
+                return Err(GameError::NoRoute { from, to });
+            }
+        }
+        let route = match orders.and_then(|orders| orders.route.as_ref()) {
+            Some(route) => route,
+            None => return Ok(None),
+        };
+
+        let speed = self.entities.characters[character_idx].cal_speed();
+        let eta = (route.remaining_distance(coor, &self.nodes) / speed).ceil() as u64;
+        Ok(Some((route, eta)))
+    }
+}
+
+#[cfg(test)]
+mod route_tests {
+    use super::*;
+    use crate::game::CharacterMobility;
+    use crate::GameOptions;
+
+    #[test]
+    fn units_follow_the_roads() {
+        let mut g = GameOptions::default()
+            .set_seed(1234)
+            .set_pruned_roads(true)
+            .build()
+            .unwrap();
+        g.colonize().unwrap();
+        g.deploy_character(0).unwrap();
+        let start = g.nearest_node(g.get_unit_coor(0).unwrap()).unwrap();
+
+        // the node farthest away along the roads
+        let (goal, path) = (0..g.nodes.len())
+            .map(|goal| (goal, g.plan_route(start, goal).unwrap()))
+            .max_by_key(|(_, path)| path.len())
+            .unwrap();
+        assert!(path.len() > 2);
+        for pair in path.windows(2) {
+            assert!(g.neighbouring_nodes(pair[0]).unwrap().contains(&pair[1]));
+        }
+
+        g.set_unit_destination(0, goal).unwrap();
+        let (route, eta) = g.unit_route(0).unwrap().unwrap();
+        assert_eq!(
+            route.waypoints,
+            path.iter().cloned().collect::<VecDeque<_>>()
+        );
+
+        // turning at every waypoint takes a few more ticks than the estimate
+        for _ in 0..eta * 2 {
+            if let CharacterMobility::Parked { node_idx } = g.entities.characters[0].mobility {
+                assert_eq!(node_idx, goal);
+                assert!(!g.unit_orders.contains_key(&0));
+                return;
+            }
+            g.update_state();
+        }
+        panic!("unit 0 should have arrived");
+    }
+
+    #[test]
+    fn advance_through_waypoints() {
+        let g = GameOptions::default().set_seed(1234).build().unwrap();
+        let path = g.plan_route(0, 1).unwrap();
+        let mut route = Route {
+            waypoints: path.iter().cloned().collect(),
+        };
+
+        let mut body = Rectangle {
+            center: g.nodes[0].coor,
+            length: 1.,
+            width: 1.,
+            angle: 0.,
+        };
+        let total = route.remaining_distance(body.center, &g.nodes);
+
+        // turning freely, the unit covers its whole movement along the roads
+        let mut steering = Steering::new(total / 2., f32::INFINITY);
+        assert!(!route.advance(&mut body, &mut steering, &g.nodes));
+        let left = route.remaining_distance(body.center, &g.nodes);
+        assert!((left - total / 2.).abs() < 0.01);
+
+        let mut steering = Steering::new(total, f32::INFINITY);
+        assert!(route.advance(&mut body, &mut steering, &g.nodes));
+        assert!(route.waypoints.is_empty());
+        assert_eq!(body.center, g.nodes[1].coor);
+    }
+}
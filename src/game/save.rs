@@ -0,0 +1,198 @@
+use crate::game::Game;
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+// save file layout: magic, format version, bincode-encoded Game
+const SAVE_MAGIC: [u8; 4] = *b"GPSV";
+
+/// bump whenever the serialized layout of Game changes
+pub const SAVE_FORMAT_VERSION: u32 = 19;
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Encoding(bincode::Error),
+    NotASaveFile,
+    UnsupportedVersion { found: u32, expected: u32 },
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "io error: {}", err),
+            SaveError::Encoding(err) => write!(f, "corrupted save data: {}", err),
+            SaveError::NotASaveFile => write!(f, "not a save file"),
+            SaveError::UnsupportedVersion { found, expected } => write!(
+                f,
+                "unsupported save format version {} (expected {})",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+impl From<bincode::Error> for SaveError {
+    fn from(err: bincode::Error) -> Self {
+        SaveError::Encoding(err)
+    }
+}
+
+/// serialize a HashMap in key order, so that equal games are encoded into identical bytes
+pub fn serialize_ordered_map<S, K, V>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Ord + Serialize,
+    V: Serialize,
+{
+    let ordered: BTreeMap<_, _> = map.iter().collect();
+    ordered.serialize(serializer)
+}
+
+/// serialize a HashSet in order, so that equal games are encoded into identical bytes
+pub fn serialize_ordered_set<S, T>(set: &HashSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Ord + Serialize,
+{
+    let ordered: BTreeSet<_> = set.iter().collect();
+    ordered.serialize(serializer)
+}
+
+/// 64-bit FNV-1a, which (unlike std's hashers) is stable across platforms and releases
+struct Fnv1a(u64);
+
+impl Write for Fnv1a {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Game {
+    /// hash of the whole game state; two games with the same checksum will evolve identically
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = Fnv1a(0xcbf2_9ce4_8422_2325);
+        bincode::serialize_into(&mut hasher, self).expect("hashing never fails");
+        hasher.0
+    }
+
+    pub fn save_to_writer<W: Write>(&self, mut writer: W) -> Result<(), SaveError> {
+        writer.write_all(&SAVE_MAGIC)?;
+        writer.write_all(&SAVE_FORMAT_VERSION.to_le_bytes())?;
+        bincode::serialize_into(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load_from_reader<R: Read>(mut reader: R) -> Result<Game, SaveError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != SAVE_MAGIC {
+            return Err(SaveError::NotASaveFile);
+        }
+
+        let mut version = [0; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != SAVE_FORMAT_VERSION {
+            return Err(SaveError::UnsupportedVersion {
+                found: version,
+                expected: SAVE_FORMAT_VERSION,
+            });
+        }
+
+        Ok(bincode::deserialize_from(reader)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        let file = File::create(path)?;
+        self.save_to_writer(BufWriter::new(file))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Game, SaveError> {
+        let file = File::open(path)?;
+        Game::load_from_reader(BufReader::new(file))
+    }
+}
+
+#[cfg(test)]
+mod save_tests {
+    use super::*;
+    use crate::game::game::colonized_game;
+    use crate::GameOptions;
+    use rand::RngCore;
+
+    #[test]
+    fn round_trip() {
+        let (mut g, _) = colonized_game(100);
+
+        let mut buffer = Vec::new();
+        g.save_to_writer(&mut buffer).unwrap();
+        let mut loaded = Game::load_from_reader(buffer.as_slice()).unwrap();
+
+        assert_eq!(g.nodes.len(), loaded.nodes.len());
+        for (a, b) in g.nodes.iter().zip(loaded.nodes.iter()) {
+            assert_eq!(a.coor, b.coor);
+            assert_eq!(a.buildings, b.buildings);
+        }
+        assert_eq!(g.building_grid, loaded.building_grid);
+        assert_eq!(g.building_sites, loaded.building_sites);
+        assert_eq!(
+            g.entities.characters.len(),
+            loaded.entities.characters.len()
+        );
+
+        // the loaded game continues with the same random sequence
+        g.colonize().unwrap();
+        loaded.colonize().unwrap();
+        for (a, b) in g.nodes.iter().zip(loaded.nodes.iter()) {
+            assert_eq!(a.buildings, b.buildings);
+        }
+        assert_eq!(g.rng.next_u32(), loaded.rng.next_u32());
+    }
+
+    #[test]
+    fn checksum_is_stable_across_save() {
+        let (mut g, _) = colonized_game(100);
+        g.colonize().unwrap();
+
+        let mut buffer = Vec::new();
+        g.save_to_writer(&mut buffer).unwrap();
+        let loaded = Game::load_from_reader(buffer.as_slice()).unwrap();
+
+        assert_eq!(g.checksum(), loaded.checksum());
+
+        g.colonize().unwrap();
+        assert_ne!(g.checksum(), loaded.checksum());
+    }
+
+    #[test]
+    fn reject_other_versions() {
+        let g = GameOptions::default().set_seed(1234).build().unwrap();
+
+        let mut buffer = Vec::new();
+        g.save_to_writer(&mut buffer).unwrap();
+        buffer[4] = buffer[4].wrapping_add(1);
+
+        match Game::load_from_reader(buffer.as_slice()) {
+            Err(SaveError::UnsupportedVersion { .. }) => {}
+            _ => panic!("expected version mismatch"),
+        }
+    }
+}
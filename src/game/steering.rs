@@ -0,0 +1,146 @@
+use crate::geometry::rectangle::Rectangle;
+use nalgebra::{distance, Point2};
+use std::f32::consts::PI;
+
+/*
+Units move like vehicles: they only go forward, along the heading of their body, and turn toward
+their target by at most their steering angle per tick:
+- a unit that doesn't face its target slows down, in proportion to the cosine of the angle it still
+  has to turn, and turns in place while the target is more than 90 degrees away
+- it also turns in place while the target is closer than its turning radius (the distance it
+  covers while turning 1 radian), so it never circles around it
+- a unit that would pass its target stops on it, and, if it was facing it, keeps the movement left
+  for its next target
+*/
+
+/// how much a unit may still move and turn during the current tick
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Steering {
+    pub movement: f32,
+    pub turn: f32, // in radian
+    turning_radius: f32,
+}
+
+/// the angle in (-PI, PI]
+fn wrap_angle(angle: f32) -> f32 {
+    let wrapped = (angle + PI).rem_euclid(2. * PI) - PI;
+    if wrapped <= -PI {
+        wrapped + 2. * PI
+    } else {
+        wrapped
+    }
+}
+
+impl Steering {
+    pub fn new(speed: f32, max_steering_angle: f32) -> Self {
+        Self {
+            movement: speed,
+            turn: max_steering_angle,
+            turning_radius: speed / max_steering_angle,
+        }
+    }
+
+    /// turn, then move a body toward `target`, spending the budget; returns true once it's there
+    pub fn steer(&mut self, body: &mut Rectangle, target: Point2<f32>) -> bool {
+        let left = distance(&body.center, &target);
+        if left == 0. {
+            return true;
+        }
+
+        let error = wrap_angle(Rectangle::angle_of(target - body.center) - body.angle);
+        let turn = error.max(-self.turn).min(self.turn);
+        body.angle = wrap_angle(body.angle + turn);
+        self.turn -= turn.abs();
+
+        let remaining_error = error - turn;
+        let step = if remaining_error != 0. && left < self.turning_radius {
+            0.
+        } else {
+            self.movement * remaining_error.cos().max(0.)
+        };
+        if step >= left {
+            body.center = target;
+            if remaining_error == 0. {
+                self.movement -= left;
+            } else {
+                self.movement = 0.;
+            }
+            return true;
+        }
+
+        body.center += body.heading() * step;
+        self.movement = 0.;
+        false
+    }
+}
+
+#[cfg(test)]
+mod steering_tests {
+    use super::*;
+
+    fn body() -> Rectangle {
+        Rectangle {
+            center: Point2::new(0., 0.),
+            length: 1.,
+            width: 1.,
+            angle: 0.,
+        }
+    }
+
+    #[test]
+    fn straight_ahead_keeps_the_rest() {
+        let mut body = body();
+        let mut steering = Steering::new(10., PI / 12.);
+        assert!(steering.steer(&mut body, Point2::new(0., 4.)));
+        assert_eq!(body.center, Point2::new(0., 4.));
+        assert_eq!(steering.movement, 6.);
+
+        assert!(!steering.steer(&mut body, Point2::new(0., 100.)));
+        assert_eq!(body.center, Point2::new(0., 10.));
+        assert_eq!(steering.movement, 0.);
+    }
+
+    #[test]
+    fn turns_at_most_the_steering_angle() {
+        let mut body = body();
+        let target = Point2::new(100., 0.);
+
+        // a quarter turn, 15 degrees per tick, slowly moving forward meanwhile
+        for tick in 1..=5 {
+            let mut steering = Steering::new(10., PI / 12.);
+            steering.steer(&mut body, target);
+            assert!((body.angle + tick as f32 * PI / 12.).abs() < 0.001);
+            assert!(steering.movement == 0. && body.center.y > 0.);
+        }
+        for _ in 0..2 {
+            Steering::new(10., PI / 12.).steer(&mut body, target);
+        }
+        let direction = (target - body.center).normalize();
+        assert!((body.heading() - direction).norm() < 0.001);
+    }
+
+    #[test]
+    fn turns_in_place_toward_a_target_behind() {
+        let mut body = body();
+        let mut steering = Steering::new(10., PI / 12.);
+        assert!(!steering.steer(&mut body, Point2::new(0., -5.)));
+        assert_eq!(body.center, Point2::new(0., 0.));
+        assert!((body.angle.abs() - PI / 12.).abs() < 0.001);
+    }
+
+    #[test]
+    fn no_circling_around_close_targets() {
+        let mut body = body();
+        let target = Point2::new(3., 0.);
+        let arrived = (0..20).any(|_| Steering::new(10., PI / 12.).steer(&mut body, target));
+        assert!(arrived);
+        assert_eq!(body.center, target);
+    }
+
+    #[test]
+    fn angles_wrap() {
+        assert!((wrap_angle(3. * PI / 2.) + PI / 2.).abs() < 0.001);
+        assert!((wrap_angle(-3. * PI / 2.) - PI / 2.).abs() < 0.001);
+        assert_eq!(wrap_angle(PI), PI);
+    }
+}
@@ -1,9 +1,10 @@
 use crate::algorithm::insertion_sort::insertion_sort;
+use crate::game::corporation::DIVIDEND_PERIOD;
+use crate::game::economy::WAGE;
+use crate::game::money::{Account, Money, TransactionKind};
+use crate::game::BuildingId;
 use crate::game::CharacterMobility;
 use crate::game::Game;
-use crate::game::MovableUnit;
-use crate::game::Projectile;
-use nalgebra::{Point2, Vector2};
 use std::collections::HashSet;
 
 impl Game {
@@ -28,56 +29,15 @@ impl Game {
     }
 
     pub fn update_state(&mut self) {
+        self.tick += 1;
+
         self.cal_computer_decisions();
 
         // update entities' location
+        self.move_units();
         let entities = &mut self.entities;
 
-        let unit_destinations = &mut self.unit_destinations;
-        let nodes = &self.nodes;
-
-        unit_destinations.retain(|unit_idx, &mut node_idx| {
-            let character = &mut entities.characters[*unit_idx];
-            let speed = character.cal_speed();
-            let node_coor = nodes[node_idx].coor;
-
-            match &mut character.mobility {
-                CharacterMobility::Unit { body, .. } => {
-                    let coor = body.center;
-                    let distance_squared = nalgebra::distance_squared(&coor, &node_coor);
-
-                    let speed_squared = speed * speed;
-
-                    if distance_squared < speed_squared {
-                        // arrival
-                        character.mobility = CharacterMobility::Parked { node_idx };
-                        false
-                    } else {
-                        let from = Vector2::new(coor.x, coor.y);
-
-                        let displacement = (node_coor - coor).normalize() * speed;
-
-                        let new_position: Point2<_> = (from + displacement).into();
-
-                        body.center = new_position;
-
-                        // if enemy units are within range (say, r) of the longest weapon, fire at them
-                        // 1. search for units within "r", filter by hostility; called this collection of units "C"
-                        // 2. turn "C" into a binary heap, sorted by distance in ascending order, called "H"
-                        // 2.1. for each weapon, if top of "H" is in range
-                        // 2.1.1 if weapon doesn't have a target, assign the target
-                        // 2.1.2 if weapon has a target but the new target has lower HP, assign the new target
-                        // 2.2 repeat until all weapon have a target or no more target in range
-                        // 2.3 fire all weapons
-
-                        true
-                    }
-                }
-                _ => unreachable!("character isn't deployed"),
-            }
-        });
-
-        for projectile in &mut entities.projectiles {}
+        for _projectile in &mut entities.projectiles {}
 
         // TODO filter out projectiles that are still moving (all 3 projectile loops should be unified into 1)
 
@@ -90,10 +50,9 @@ impl Game {
             {
                 let xs: HashSet<_> = self.unit_x_axis.iter().cloned().collect();
                 let ys: HashSet<_> = self.unit_y_axis.iter().cloned().collect();
-                dbg!(xs.len() == self.unit_x_axis.len());
-                dbg!(ys.len() == self.unit_y_axis.len());
-
-                assert!(xs == ys);
+                debug_assert_eq!(xs.len(), self.unit_x_axis.len());
+                debug_assert_eq!(ys.len(), self.unit_y_axis.len());
+                debug_assert!(xs == ys);
             }
 
             self.unit_x_axis
@@ -110,10 +69,7 @@ impl Game {
             {
                 let xs: HashSet<_> = self.unit_x_axis.iter().cloned().collect();
                 let ys: HashSet<_> = self.unit_y_axis.iter().cloned().collect();
-                dbg!(&xs);
-                dbg!(&ys);
-
-                assert!(xs == ys);
+                debug_assert!(xs == ys);
             }
 
             insertion_sort(&mut self.unit_x_axis, |a, b| {
@@ -123,18 +79,78 @@ impl Game {
                 entities.unit_comparator_by_y(*a, *b)
             });
 
-            if self.unit_x_axis.len() == self.unit_y_axis.len() {
+            if self.unit_x_axis.len() != self.unit_y_axis.len() {
                 dbg!(&self.unit_x_axis);
                 dbg!(&self.unit_y_axis);
                 panic!("number of units in x- and y-axis should match");
             }
         }
 
-        for node in &mut self.nodes {
+        // units that moved into each other are pushed apart
+        self.resolve_collisions();
+
+        let buildings = &mut self.entities.buildings;
+        let recipes = &self.recipes;
+        let bonuses = &self.bonuses;
+        let building_grid = &self.building_grid;
+        let building_sites = &self.building_sites;
+        let ledger = &mut self.ledger;
+        let tick = self.tick;
+        let businesses: HashSet<BuildingId> = self
+            .corporations
+            .iter()
+            .flat_map(|corporation| corporation.buildings())
+            .collect();
+        for (node_idx, node) in self.nodes.iter_mut().enumerate() {
+            let city = Account::City(node_idx);
+            let owner = self
+                .nations
+                .iter()
+                .position(|nation| nation.cities.contains(&node_idx))
+                .map(Account::Nation);
+
             // industry buy
             // industry production
+            let output_bonuses = node.output_bonuses(bonuses, building_grid, building_sites);
+            let mut produced = Vec::new();
+            node.produce(buildings, &output_bonuses, &mut produced);
+            let num_workers =
+                node.run_industries(buildings, recipes, &output_bonuses, &mut produced);
+            // businesses sell what they produced to the market, at its price once they're done
+            for (building, commodity, qty) in produced {
+                if businesses.contains(&building) {
+                    let value = Money::from_credits(qty as f32 * node.price(commodity));
+                    let business = Account::Building(building);
+                    ledger.transfer(tick, TransactionKind::Sale, city, business, value);
+                }
+            }
+            if let Some(owner) = owner {
+                let wages = Money::from_credits(num_workers as f32 * WAGE);
+                ledger.transfer(tick, TransactionKind::Wages, owner, city, wages);
+            }
             // industry sell
+            node.run_warehouses(buildings, ledger, city, tick);
             // civilian consumption
+            let unfed = node.consume_food();
+            // births and deaths, in colonized nodes only, and the people move into their homes
+            if !node.buildings.is_empty() {
+                node.update_population(unfed);
+                node.house_people(buildings);
+            }
+            // market fees, taxed from the city by its nation, as far as the city can pay
+            let fee = Money::from_credits(node.run_market(buildings))
+                .min(ledger.balance(city).max(Money::ZERO));
+            if let Some(owner) = owner {
+                ledger.transfer(tick, TransactionKind::Tax, city, owner, fee);
+            }
+            node.close_market();
+        }
+
+        // institutions spend the cities' cash, after the day's wages and trades
+        self.run_institutions();
+
+        if self.tick.is_multiple_of(DIVIDEND_PERIOD) {
+            self.pay_dividends();
         }
     }
 }
@@ -0,0 +1,327 @@
+use crate::game::money::{Account, Ledger, Money, TransactionKind};
+use crate::game::BuildingId;
+use crate::game::Buildings;
+use crate::game::Commodity;
+use crate::game::Game;
+use crate::game::GameError;
+use crate::game::Node;
+use crate::game::Warehouse;
+
+/*
+Patrician-style trading: every tick, for each commodity, a warehouse
+- buys from its node's market, one unit at a time, while its storage is below the demand quantity
+  and the price is at most the import price
+- then sells its storage back, one unit at a time, while the price is at least the sale price
+
+A sale price of 0 means "not for sale". Buying raises the node's price and selling lowers it, so
+trading stops by itself once the price crosses the limit. Purchases also count towards the node's
+demand, which keeps its prices up over the following tick.
+*/
+
+/// number of trades remembered by a warehouse
+pub const LEDGER_LENGTH: usize = 50;
+
+/// credits paid to the World by every warehouse, every tick
+pub const WAREHOUSE_UPKEEP: f32 = 1.;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// all units of a commodity traded by a warehouse in one tick
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Trade {
+    pub tick: u64,
+    pub side: Side,
+    pub commodity: Commodity,
+    pub qty: u32,
+    pub total: f32, // paid or received
+}
+
+/// a per-commodity setting of a warehouse
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WarehouseSetting {
+    DemandQty(u32),
+    ImportPrice(f32),
+    SalePrice(f32),
+}
+
+impl Warehouse {
+    pub fn storage(&self, commodity: Commodity) -> u32 {
+        self.storage[commodity]
+    }
+
+    pub fn demand_qty(&self, commodity: Commodity) -> u32 {
+        self.demand_qtys[commodity]
+    }
+
+    pub fn import_price(&self, commodity: Commodity) -> f32 {
+        self.import_prices[commodity]
+    }
+
+    pub fn sale_price(&self, commodity: Commodity) -> f32 {
+        self.sale_prices[commodity]
+    }
+
+    /// latest trades, oldest first
+    pub fn ledger(&self) -> impl Iterator<Item = &Trade> {
+        self.ledger.iter()
+    }
+
+    /// returns the trades of the tick, for the warehouse and the city to settle
+    pub(crate) fn trade(&mut self, node: &mut Node, tick: u64) -> Vec<Trade> {
+        let mut trades = Vec::new();
+        let commodities: Vec<_> = self
+            .storage
+            .iter()
+            .map(|(commodity, _)| commodity)
+            .collect();
+
+        for commodity in commodities {
+            let (mut qty, mut total) = (0, 0.);
+            while self.storage[commodity] < self.demand_qtys[commodity]
+                && node.market[commodity] > 0
+            {
+                let price = node.price(commodity);
+                if price > self.import_prices[commodity] {
+                    break;
+                }
+                node.take(commodity, 1);
+                self.storage[commodity] += 1;
+                qty += 1;
+                total += price;
+            }
+            trades.extend(self.record(tick, Side::Buy, commodity, qty, total));
+
+            let (mut qty, mut total) = (0, 0.);
+            while self.storage[commodity] > 0 && self.sale_prices[commodity] > 0. {
+                let price = node.price(commodity);
+                if price < self.sale_prices[commodity] {
+                    break;
+                }
+                node.market[commodity] += 1;
+                self.storage[commodity] -= 1;
+                qty += 1;
+                total += price;
+            }
+            trades.extend(self.record(tick, Side::Sell, commodity, qty, total));
+        }
+
+        trades
+    }
+
+    fn record(
+        &mut self,
+        tick: u64,
+        side: Side,
+        commodity: Commodity,
+        qty: u32,
+        total: f32,
+    ) -> Option<Trade> {
+        if qty == 0 {
+            return None;
+        }
+
+        let trade = Trade {
+            tick,
+            side,
+            commodity,
+            qty,
+            total,
+        };
+        if self.ledger.len() == LEDGER_LENGTH {
+            self.ledger.pop_front();
+        }
+        self.ledger.push_back(trade);
+        Some(trade)
+    }
+}
+
+impl Node {
+    /// warehouses trade in id order, so that the first one gets the best prices; they pay the
+    /// city for purchases, get paid for sales, and pay their upkeep
+    pub(crate) fn run_warehouses(
+        &mut self,
+        buildings: &mut Buildings,
+        ledger: &mut Ledger,
+        city: Account,
+        tick: u64,
+    ) {
+        let mut warehouses: Vec<_> = self
+            .buildings
+            .iter()
+            .filter_map(|building| match building {
+                BuildingId::Warehouse(handle) => Some(*handle),
+                _ => None,
+            })
+            .collect();
+        warehouses.sort_unstable();
+
+        for handle in warehouses {
+            let warehouse = match buildings.warehouses.get_mut(handle) {
+                Some(warehouse) => warehouse,
+                None => continue,
+            };
+            let account = Account::Building(BuildingId::Warehouse(handle));
+
+            for trade in warehouse.trade(self, tick) {
+                let amount = Money::from_credits(trade.total);
+                match trade.side {
+                    Side::Buy => {
+                        ledger.transfer(tick, TransactionKind::Purchase, account, city, amount)
+                    }
+                    Side::Sell => {
+                        ledger.transfer(tick, TransactionKind::Sale, city, account, amount)
+                    }
+                }
+            }
+
+            let upkeep = Money::from_credits(WAREHOUSE_UPKEEP);
+            ledger.transfer(
+                tick,
+                TransactionKind::Upkeep,
+                account,
+                Account::World,
+                upkeep,
+            );
+        }
+    }
+}
+
+impl Game {
+    pub fn warehouse(&self, building_id: BuildingId) -> Result<&Warehouse, GameError> {
+        match building_id {
+            BuildingId::Warehouse(handle) => self
+                .entities
+                .buildings
+                .warehouses
+                .get(handle)
+                .ok_or(GameError::NoSuchBuilding(building_id)),
+            _ => Err(GameError::NotAWarehouse(building_id)),
+        }
+    }
+
+    pub fn configure_warehouse(
+        &mut self,
+        building_id: BuildingId,
+        commodity: Commodity,
+        setting: WarehouseSetting,
+    ) -> Result<(), GameError> {
+        self.warehouse(building_id)?;
+
+        match setting {
+            WarehouseSetting::ImportPrice(price) | WarehouseSetting::SalePrice(price)
+                if !(price.is_finite() && price >= 0.) =>
+            {
+                return Err(GameError::InvalidPrice(price));
+            }
+            _ => {}
+        }
+
+        let warehouse = match building_id {
+            BuildingId::Warehouse(handle) => self.entities.buildings.warehouses.get_mut(handle),
+            _ => None,
+        }
+        .expect("bug: the warehouse has been checked");
+
+        match setting {
+            WarehouseSetting::DemandQty(qty) => warehouse.demand_qtys[commodity] = qty,
+            WarehouseSetting::ImportPrice(price) => warehouse.import_prices[commodity] = price,
+            WarehouseSetting::SalePrice(price) => warehouse.sale_prices[commodity] = price,
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod warehouse_tests {
+    use super::*;
+    use crate::game::game::colonized_game;
+
+    fn game_with_warehouse() -> (Game, usize, BuildingId) {
+        let (mut g, node_idx) = colonized_game(100);
+        let coor = g.nodes[node_idx].coor;
+        let warehouse = g
+            .create_warehouse([coor.x as u32 + 3, coor.y as u32])
+            .unwrap();
+        (g, node_idx, warehouse)
+    }
+
+    #[test]
+    fn buy_below_import_price() {
+        let (mut g, node_idx, warehouse) = game_with_warehouse();
+        g.nodes[node_idx].market[Commodity::Steel] = 100;
+
+        g.configure_warehouse(warehouse, Commodity::Steel, WarehouseSetting::DemandQty(30))
+            .unwrap();
+        // the price rises from 13.3 as the warehouse buys; at most 14 allows a few units
+        g.configure_warehouse(
+            warehouse,
+            Commodity::Steel,
+            WarehouseSetting::ImportPrice(14.),
+        )
+        .unwrap();
+        g.update_state();
+
+        let bought = g.warehouse(warehouse).unwrap().storage(Commodity::Steel);
+        assert!(bought > 0 && bought < 30);
+        assert_eq!(g.nodes[node_idx].market[Commodity::Steel], 100 - bought);
+        assert!(g.nodes[node_idx].price(Commodity::Steel) > 14. - 0.3);
+        let account = Account::Building(warehouse);
+        assert!(g.ledger.balance(account) < Money::ZERO);
+    }
+
+    #[test]
+    fn sell_above_sale_price() {
+        let (mut g, node_idx, warehouse) = game_with_warehouse();
+        g.nodes[node_idx].market[Commodity::Steel] = 10;
+
+        let settings = [
+            WarehouseSetting::DemandQty(5),
+            WarehouseSetting::ImportPrice(100.),
+        ];
+        for &setting in &settings {
+            g.configure_warehouse(warehouse, Commodity::Steel, setting)
+                .unwrap();
+        }
+        g.update_state();
+        assert_eq!(g.warehouse(warehouse).unwrap().storage(Commodity::Steel), 5);
+
+        g.configure_warehouse(warehouse, Commodity::Steel, WarehouseSetting::SalePrice(1.))
+            .unwrap();
+        g.configure_warehouse(warehouse, Commodity::Steel, WarehouseSetting::DemandQty(0))
+            .unwrap();
+        g.update_state();
+
+        let w = g.warehouse(warehouse).unwrap();
+        assert_eq!(w.storage(Commodity::Steel), 0);
+        let sides: Vec<_> = w.ledger().map(|trade| trade.side).collect();
+        assert_eq!(sides, vec![Side::Buy, Side::Sell]);
+    }
+
+    #[test]
+    fn reject_invalid_settings() {
+        let (mut g, _, warehouse) = game_with_warehouse();
+        assert_eq!(
+            g.configure_warehouse(
+                warehouse,
+                Commodity::Steel,
+                WarehouseSetting::SalePrice(-1.)
+            ),
+            Err(GameError::InvalidPrice(-1.))
+        );
+
+        let landing = *g
+            .building_grid
+            .values()
+            .find(|id| **id != warehouse)
+            .unwrap();
+        assert_eq!(
+            g.configure_warehouse(landing, Commodity::Steel, WarehouseSetting::DemandQty(1)),
+            Err(GameError::NotAWarehouse(landing))
+        );
+    }
+}
@@ -2,3 +2,4 @@ pub mod circle;
 pub mod circle_rectangle;
 pub mod predict_intersection;
 pub mod rectangle;
+pub mod rectangle_rectangle;
@@ -55,7 +55,7 @@ fn predict_intersection_time(
                 OrderedFloat((neg_b + sqrt_discriminant) / two_a),
                 OrderedFloat((neg_b - sqrt_discriminant) / two_a),
             ]
-            .into_iter()
+            .iter()
             .filter(|OrderedFloat(t)| t.is_positive())
             .min()
             .cloned()
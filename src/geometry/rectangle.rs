@@ -1,7 +1,7 @@
 use nalgebra::geometry::Rotation2;
 use nalgebra::{Point2, Vector2};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Rectangle {
     pub center: Point2<f32>,
     pub length: f32,
@@ -77,6 +77,16 @@ impl Rectangle {
         const EPSILON: f32 = 0.00001;
         self.is_intersect_point_with_epsilon(point, EPSILON)
     }
+
+    /// unit vector along the length, where the rectangle is heading; (0,1) when the angle is 0
+    pub fn heading(&self) -> Vector2<f32> {
+        Rotation2::new(self.angle) * Vector2::y()
+    }
+
+    /// angle that heads along `direction`
+    pub fn angle_of(direction: Vector2<f32>) -> f32 {
+        (-direction.x).atan2(direction.y)
+    }
 }
 
 #[cfg(test)]
@@ -206,4 +216,21 @@ mod rectangle_tests {
         assert!(!rect.is_intersect_point(Point2::new(2.5, -5.)));
         assert!(!rect.is_intersect_point(Point2::new(-2.5, 5.)));
     }
+
+    #[test]
+    fn heading_follows_angle() {
+        let mut rect = Rectangle {
+            center: Point2::new(0., 0.),
+            length: 2.,
+            width: 1.,
+            angle: 0.,
+        };
+        assert_eq!(rect.heading(), Vector2::new(0., 1.));
+
+        rect.angle = Rectangle::angle_of(Vector2::new(1., 0.));
+        assert!((rect.heading() - Vector2::new(1., 0.)).norm() < 0.0001);
+        // the length points along the heading
+        let [_, _, c, d] = rect.get_transformed_vertices();
+        assert!((c.x - d.x).abs() < 0.0001);
+    }
 }
@@ -0,0 +1,109 @@
+use super::rectangle::Rectangle;
+use nalgebra::{Point2, Vector2};
+
+/// min and max of the vertices projected on an axis
+fn project(vertices: &[Point2<f32>; 4], axis: Vector2<f32>) -> (f32, f32) {
+    vertices
+        .iter()
+        .map(|vertex| vertex.coords.dot(&axis))
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), x| {
+            (min.min(x), max.max(x))
+        })
+}
+
+/// the shortest translation that moves `b` out of `a`, if they overlap; rectangles that only touch
+/// don't
+pub fn penetration(a: &Rectangle, b: &Rectangle) -> Option<Vector2<f32>> {
+    // separating axis theorem: two rectangles are disjoint if and only if their projections are on
+    // the normal of one of their edges
+    let a_vertices = a.get_transformed_vertices();
+    let b_vertices = b.get_transformed_vertices();
+    let edges = [
+        a_vertices[1] - a_vertices[0],
+        a_vertices[2] - a_vertices[1],
+        b_vertices[1] - b_vertices[0],
+        b_vertices[2] - b_vertices[1],
+    ];
+
+    let mut shortest: Option<Vector2<f32>> = None;
+    for edge in edges.iter() {
+        let axis = match edge.try_normalize(0.) {
+            Some(axis) => axis,
+            None => continue, // flat rectangle
+        };
+        let (a_min, a_max) = project(&a_vertices, axis);
+        let (b_min, b_max) = project(&b_vertices, axis);
+
+        let forward = a_max - b_min; // moving b along the axis
+        let backward = b_max - a_min; // moving b against it
+        if forward <= 0. || backward <= 0. {
+            return None;
+        }
+
+        let translation = if forward <= backward {
+            axis * forward
+        } else {
+            -axis * backward
+        };
+        let shorter = match shortest {
+            Some(shortest) => translation.norm() < shortest.norm(),
+            None => true,
+        };
+        if shorter {
+            shortest = Some(translation);
+        }
+    }
+    shortest
+}
+
+pub fn is_intersect(a: &Rectangle, b: &Rectangle) -> bool {
+    penetration(a, b).is_some()
+}
+
+#[cfg(test)]
+mod rectangle_rectangle_tests {
+    use super::*;
+
+    fn square(x: f32, y: f32, angle: f32) -> Rectangle {
+        Rectangle {
+            center: Point2::new(x, y),
+            length: 2.,
+            width: 2.,
+            angle,
+        }
+    }
+
+    #[test]
+    fn aligned_squares() {
+        let a = square(0., 0., 0.);
+        assert!(is_intersect(&a, &square(1.5, 0.5, 0.)));
+        // touching, and apart
+        assert!(!is_intersect(&a, &square(2., 0., 0.)));
+        assert!(!is_intersect(&a, &square(0., -3., 0.)));
+
+        let translation = penetration(&a, &square(1.5, 0.5, 0.)).unwrap();
+        assert!((translation - Vector2::new(0.5, 0.)).norm() < 0.0001);
+        let translation = penetration(&a, &square(-0.5, -1.5, 0.)).unwrap();
+        assert!((translation - Vector2::new(0., -0.5)).norm() < 0.0001);
+    }
+
+    #[test]
+    fn rotated_squares() {
+        let a = square(0., 0., 0.);
+        let diamond = std::f32::consts::FRAC_PI_4;
+
+        // the bounding boxes overlap, but the corner of the diamond doesn't reach the square's
+        assert!(!is_intersect(&a, &square(2.3, 2.3, diamond)));
+        // its corner pokes into the square's side
+        assert!(is_intersect(&a, &square(2.3, 0., diamond)));
+        let translation = penetration(&a, &square(2.3, 0., diamond)).unwrap();
+        assert!((translation - Vector2::new(2_f32.sqrt() + 1. - 2.3, 0.)).norm() < 0.0001);
+    }
+
+    #[test]
+    fn same_center() {
+        let a = square(0., 0., 0.);
+        let translation = penetration(&a, &a).unwrap();
+        assert!((translation.norm() - 2.).abs() < 0.0001);
+    }
+}
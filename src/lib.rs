@@ -0,0 +1,23 @@
+extern crate bincode;
+#[macro_use]
+extern crate enum_map;
+#[macro_use]
+extern crate log;
+extern crate nalgebra;
+extern crate num;
+extern crate ordered_float;
+extern crate rand;
+extern crate rand_hc;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate spade;
+extern crate toml;
+
+pub mod algorithm;
+pub mod game;
+pub mod geometry;
+mod options;
+
+pub use crate::game::{Game, GameError};
+pub use crate::options::{GameOptions, OptionsError};
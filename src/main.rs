@@ -1,282 +1,322 @@
-extern crate regex;
-#[macro_use]
-extern crate enum_map;
-extern crate nalgebra;
-extern crate num;
-extern crate ordered_float;
+extern crate getopts;
 extern crate rand;
-extern crate spade;
+extern crate simple_logger;
 #[macro_use]
 extern crate log;
-extern crate getopts;
-extern crate rand_hc;
-extern crate simple_logger;
+extern crate new;
 
-mod algorithm;
 mod command;
-mod game;
-mod geometry;
-
-use crate::command::character_enter_city;
-use crate::command::create_farm;
-use crate::command::create_warehouse;
-use crate::command::deploy_character;
-use crate::command::estimate_nearby_objects;
-use crate::command::exit;
-use crate::command::land_colony_ship;
-use crate::command::list_characters;
-use crate::command::list_nations;
-use crate::command::list_nodes;
-use crate::command::list_units;
-use crate::command::move_unit;
-use crate::command::step;
-use crate::game::game::CITY_RADIUS;
-use crate::game::game::CITY_RADIUS_SQUARED;
-use crate::game::Commodity;
-use crate::game::Game;
-use crate::game::Node;
-use getopts::Options;
-use nalgebra::distance_squared;
-use nalgebra::Point2;
+mod parser;
+mod replay;
+mod script;
+
+use crate::command::execute;
+use crate::parser::Command;
+use crate::replay::{Recorder, Replay, ReplayStep};
+use getopts::{Matches, Options};
+use new::Game;
+use new::GameOptions;
+use new::OptionsError;
 use rand::Rng;
-use rand::SeedableRng;
-use rand_hc::Hc128Rng;
-use regex::{Regex, RegexSet};
 use std::env;
 use std::io::{self, BufRead};
+use std::process;
+use std::str::FromStr;
 
-struct GameOptions {
-    num_nodes: usize,
-    dimension: (f32, f32),
-    starting_population: usize,
-    seed: Option<u64>,
-}
-
-impl Default for GameOptions {
-    fn default() -> Self {
-        Self {
-            num_nodes: 10,
-            dimension: (300., 300.),
-            starting_population: 100,
-            seed: None,
-        }
-    }
-}
-
-impl GameOptions {
-    pub fn build(self) -> Game {
-        let Self {
-            num_nodes,
-            dimension,
-            starting_population,
-            seed,
-        } = self;
-
-        let (width, height) = dimension;
+fn game_loop(mut g: Game, mut recorder: Option<Recorder>) {
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line.unwrap();
 
-        let mut rng = match seed {
-            Some(state) => Hc128Rng::seed_from_u64(state),
-            None => Hc128Rng::from_rng(rand::thread_rng()).unwrap(),
+        let command = match parser::parse(&line) {
+            Ok(command) => command,
+            Err(err) => {
+                println!("invalid command: {}", line);
+                println!("{}", err);
+                continue;
+            }
         };
 
-        let nodes = {
-            let mut try_counter = 0;
-            let max_tries = 2 * num_nodes;
+        let is_session_command = command.is_session_command();
+        let is_load = matches!(command, Command::Load { .. });
 
-            let mut coor_candidates = Vec::with_capacity(100);
-            let two_city_radius = CITY_RADIUS + CITY_RADIUS;
-            while coor_candidates.len() < num_nodes {
-                assert!(
-                    try_counter < max_tries,
-                    "exceed maximum number of tries in node generation"
-                );
-
-                try_counter += 1;
-
-                let coor = {
-                    let x = rng.gen_range(two_city_radius, width - two_city_radius);
-                    let y = rng.gen_range(two_city_radius, height - two_city_radius);
-                    Point2::new(x, y)
-                };
-
-                let is_overlapping = coor_candidates
-                    .iter()
-                    .any(|other_coor| distance_squared(other_coor, &coor) < CITY_RADIUS_SQUARED);
-
-                if is_overlapping {
-                    continue;
-                }
-                coor_candidates.push(coor);
+        let is_exit = match execute(&mut g, command) {
+            Ok(is_exit) => is_exit,
+            Err(err) => {
+                println!("{}", err);
+                false
             }
-
-            coor_candidates
-                .iter()
-                .map(|&coor| Node {
-                    coor,
-                    population: starting_population,
-                    institutions: Default::default(),
-                    market: Default::default(),
-                    resources: [
-                        Commodity::Grain,
-                        Commodity::Steel,
-                        Commodity::Fruit,
-                        Commodity::Meat,
-                    ],
-                    buildings: Default::default(),
-                })
-                .collect()
         };
 
-        Game {
-            nodes,
-            rng,
-
-            entities: Default::default(),
-            nations: Default::default(),
-
-            unit_destinations: Default::default(),
-            building_grid: Default::default(),
-
-            unit_nodes: Default::default(),
-            unit_x_axis: Default::default(),
-            unit_y_axis: Default::default(),
+        if is_load && recorder.is_some() {
+            println!("recording stopped: a loaded game cannot be replayed from its seed");
+            recorder = None;
         }
-    }
 
-    pub fn set_num_nodes(self, num_nodes: usize) -> Self {
-        Self { num_nodes, ..self }
-    }
+        if !is_session_command {
+            if let Some(r) = &mut recorder {
+                if let Err(err) = r.record(&g, &line) {
+                    println!("recording stopped: {}", err);
+                    recorder = None;
+                }
+            }
+        }
 
-    pub fn set_seed(self, state: u64) -> Self {
-        Self {
-            seed: Some(state),
-            ..self
+        if is_exit {
+            break;
         }
     }
+    println!("exited normally");
 }
 
-type CommandDispatcher = dyn for<'r> std::ops::Fn(&'r mut Game, &Regex, &str) -> bool + 'static;
-type RawCommands = std::vec::Vec<(std::string::String, &'static CommandDispatcher)>;
-
-fn game_loop(mut g: Game) {
-    let commands: RawCommands = {
-        const FLOAT_REGEX: &str = r"-?\d+\.\d+|-?\d+";
-        let coor_regex = format!(r"\(({}),({})\)", FLOAT_REGEX, FLOAT_REGEX);
-        let estimate_nearby_objects_regex =
-            format!("^rough nearby objects {} ({})$", coor_regex, FLOAT_REGEX);
-        let create_farm_regex = format!("^create farm at {}$", coor_regex);
-        let create_warehouse_regex = format!("^create warehouse at {}$", coor_regex);
-
-        vec![
-            ("^exit$".to_owned(), &exit),
-            ("^step$".to_owned(), &step),
-            ("^list nodes$".to_owned(), &list_nodes),
-            (r"^move unit (\d+) to node (\d+)$".to_owned(), &move_unit),
-            (
-                estimate_nearby_objects_regex.to_owned(),
-                &estimate_nearby_objects,
-            ),
-            (create_farm_regex, &create_farm),
-            (create_warehouse_regex, &create_warehouse),
-            ("^land colony ship$".to_owned(), &land_colony_ship),
-            ("^list characters$".to_owned(), &list_characters),
-            ("^list nations$".to_owned(), &list_nations),
-            (r"^deploy character (\d+)$".to_owned(), &deploy_character),
-            (
-                r"^character (\d+) enter city$".to_owned(),
-                &character_enter_city,
-            ),
-            (r"list units".to_owned(), &list_units),
-        ]
+/// re-execute a recorded session; returns false if the replay is invalid or diverges
+fn replay_loop(replay: Replay) -> bool {
+    let Replay { options, steps } = replay;
+    let mut g = match options.build() {
+        Ok(g) => g,
+        Err(err) => {
+            println!("replay: {}", err);
+            return false;
+        }
     };
 
-    let regexes: Vec<_> = commands
-        .iter()
-        .map(|(pattern, _)| Regex::new(pattern).unwrap())
-        .collect();
-
-    let patterns = commands.iter().map(|(pattern, _)| pattern);
-
-    let set = RegexSet::new(patterns).unwrap();
-
-    let mut match_list = Vec::with_capacity(commands.len());
-
-    let stdin = io::stdin();
-    for line in stdin.lock().lines() {
-        match_list.clear();
-        let line = line.unwrap();
+    for (step_idx, ReplayStep { command, checksum }) in steps.iter().enumerate() {
+        let parsed = match parser::parse(command) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                println!(
+                    "replay: invalid command #{} ({}): {}",
+                    step_idx, command, err
+                );
+                return false;
+            }
+        };
 
-        match_list.extend(set.matches(&line).into_iter());
-        assert!(match_list.len() <= 1, "bug: ambiguous grammar for commands");
+        let is_exit = match execute(&mut g, parsed) {
+            Ok(is_exit) => is_exit,
+            Err(err) => {
+                println!("{}", err);
+                false
+            }
+        };
 
-        if match_list.is_empty() {
-            println!("invalid command: {}", line);
-            continue;
+        if let Some(expected) = checksum {
+            let actual = g.checksum();
+            if actual != *expected {
+                println!(
+                    "replay diverged at command #{} ({}): expected checksum {:016x}, got {:016x}",
+                    step_idx, command, expected, actual
+                );
+                return false;
+            }
         }
 
-        let idx = match_list[0];
-        let dispatcher = commands[idx].1;
-        let regex = &regexes[idx];
-
-        if dispatcher(&mut g, regex, &line) {
+        if is_exit {
             break;
         }
     }
-    println!("exited normally");
+
+    println!("replayed {} commands", steps.len());
+    true
 }
 
-fn main() {
-    fn do_work(inp: &str, out: Option<String>) {
-        dbg!(format!("{}", 123232 + 324234));
-        println!("input: {}", inp);
-        match out {
-            Some(x) => println!("output: {}", x),
-            None => println!("No Output"),
+/// game options from defaults, then the config file, then the command line flags
+fn parse_game_options(matches: &Matches) -> Result<GameOptions, OptionsError> {
+    fn parse_opt<T: FromStr>(
+        matches: &Matches,
+        option: &'static str,
+    ) -> Result<Option<T>, OptionsError> {
+        match matches.opt_str(option) {
+            Some(value) => match value.parse() {
+                Ok(parsed) => Ok(Some(parsed)),
+                Err(_) => Err(OptionsError::InvalidValue { option, value }),
+            },
+            None => Ok(None),
         }
     }
 
+    let mut options = GameOptions::default();
+
+    if let Some(path) = matches.opt_str("config") {
+        options = options.apply_config_file(path)?;
+    }
+    if let Some(num_nodes) = parse_opt(matches, "num-nodes")? {
+        options = options.set_num_nodes(num_nodes);
+    }
+    let (width, height) = options.dimension();
+    let width = parse_opt(matches, "width")?.unwrap_or(width);
+    let height = parse_opt(matches, "height")?.unwrap_or(height);
+    options = options.set_dimension((width, height));
+    if let Some(starting_population) = parse_opt(matches, "starting-population")? {
+        options = options.set_starting_population(starting_population);
+    }
+    if let Some(seed) = parse_opt(matches, "seed")? {
+        options = options.set_seed(seed);
+    }
+    if let Some(path) = matches.opt_str("recipes") {
+        options = options.set_recipes_file(path);
+    }
+    if let Some(path) = matches.opt_str("bonuses") {
+        options = options.set_bonuses_file(path);
+    }
+    if matches.opt_present("pruned-roads") {
+        options = options.set_pruned_roads(true);
+    }
+
+    options.validate()?;
+    Ok(options)
+}
+
+fn main() {
     fn print_usage(program: &str, opts: Options) {
         let brief = format!("Usage: {} [options]", program);
         print!("{}", opts.usage(&brief));
     }
 
-    simple_logger::init().unwrap();
-
     let args: Vec<String> = env::args().collect();
 
-    println!("args: {:?}", args);
-
-    let args: Vec<String> = env::args().collect();
     let program = args[0].clone();
 
     let mut opts = Options::new();
+    opts.optopt("", "config", "read game options from a TOML file", "FILE");
+    opts.optopt(
+        "",
+        "num-nodes",
+        "set number of nodes in the world",
+        "integer",
+    );
+    opts.optopt("", "width", "set width of the world", "number");
+    opts.optopt("", "height", "set height of the world", "number");
+    opts.optopt(
+        "",
+        "starting-population",
+        "set population of every node",
+        "integer",
+    );
     opts.optopt("", "seed", "set seed for the game", "integer");
+    opts.optopt(
+        "",
+        "recipes",
+        "read production recipes from a TOML file",
+        "FILE",
+    );
+    opts.optopt(
+        "",
+        "bonuses",
+        "read production bonuses from a TOML file",
+        "FILE",
+    );
+    opts.optflag(
+        "",
+        "pruned-roads",
+        "keep only the shortest roads between nodes (relative neighbourhood graph)",
+    );
+    opts.optopt(
+        "",
+        "record",
+        "record accepted commands into a replay file",
+        "FILE",
+    );
+    opts.optflag(
+        "",
+        "checksum",
+        "record the game state checksum after every command",
+    );
+    opts.optopt(
+        "",
+        "replay",
+        "re-execute a replay file without reading stdin",
+        "FILE",
+    );
+    opts.optopt(
+        "",
+        "script",
+        "run a script of commands and expectations without reading stdin",
+        "FILE",
+    );
     opts.optflag("h", "help", "print this help menu");
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => m,
-        Err(f) => panic!(f.to_string()),
+        Err(f) => {
+            println!("{}", f);
+            print_usage(&program, opts);
+            process::exit(1);
+        }
     };
     if matches.opt_present("h") {
         print_usage(&program, opts);
         return;
     }
 
-    let options = GameOptions::default().set_num_nodes(15);
+    // scripts are checked against golden output, which mustn't contain timestamps
+    let script = matches.opt_str("script");
+    match script {
+        Some(_) => simple_logger::init_with_level(log::Level::Warn).unwrap(),
+        None => simple_logger::init().unwrap(),
+    }
 
-    /*
-    let options = match matches.opt_str("seed") {
-        Some(seed) => match seed.parse() {
-            Ok(state) => options.set_seed(state),
+    if let Some(path) = matches.opt_str("replay") {
+        let replay = match Replay::load(&path) {
+            Ok(replay) => replay,
             Err(err) => {
-                panic!(err.to_string());
+                println!("cannot load replay {}: {}", path, err);
+                process::exit(1);
             }
-        },
-        None => options,
+        };
+        if !replay_loop(replay) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    let options = match parse_game_options(&matches) {
+        Ok(options) => options,
+        Err(err) => {
+            println!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    // pick the seed here, so that the session can be reproduced from the echoed options
+    let options = match options.seed() {
+        Some(_) => options,
+        None => options.set_seed(rand::thread_rng().gen()),
     };
-    */
+    println!("options: {}", options);
 
-    let seed: u64 = rand::thread_rng().gen();
-    println!("seed={}", seed);
+    let g = match options.clone().build() {
+        Ok(g) => g,
+        Err(err) => {
+            println!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    if let Some(path) = script {
+        let mut g = g;
+        match script::run_script(&mut g, &path) {
+            Ok(true) => return,
+            Ok(false) => process::exit(1),
+            Err(err) => {
+                println!("cannot read script {}: {}", path, err);
+                process::exit(1);
+            }
+        }
+    }
+
+    let recorder = match matches.opt_str("record") {
+        Some(path) => {
+            let with_checksum = matches.opt_present("checksum");
+            match Recorder::create(&path, &options, g.rng.seed(), with_checksum) {
+                Ok(recorder) => Some(recorder),
+                Err(err) => {
+                    println!("cannot create replay file {}: {}", path, err);
+                    process::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
 
-    game_loop(options.set_seed(1026304851583305830).build());
+    game_loop(g, recorder);
 }
@@ -0,0 +1,441 @@
+use crate::game::bonus::{BonusError, Bonuses};
+use crate::game::game::CITY_RADIUS;
+use crate::game::game::CITY_RADIUS_SQUARED;
+use crate::game::recipe::{RecipeError, Recipes};
+use crate::game::rng::GameRng;
+use crate::game::road::Roads;
+use crate::game::Commodity;
+use crate::game::Game;
+use crate::game::Node;
+use nalgebra::distance_squared;
+use nalgebra::Point2;
+use rand::Rng;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum OptionsError {
+    InvalidValue { option: &'static str, value: String },
+    NoNodes,
+    DimensionTooSmall { min: f32 },
+    TooManyNodes { max: usize },
+    NodeGenerationFailed,
+    ConfigIo(io::Error),
+    ConfigFormat(toml::de::Error),
+    Recipes(RecipeError),
+    Bonuses(BonusError),
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionsError::InvalidValue { option, value } => {
+                write!(f, "invalid value for {}: {}", option, value)
+            }
+            OptionsError::NoNodes => write!(f, "the world needs at least 1 node"),
+            OptionsError::DimensionTooSmall { min } => {
+                write!(f, "width and height must be larger than {}", min)
+            }
+            OptionsError::TooManyNodes { max } => {
+                write!(f, "too many nodes for the dimension (at most {})", max)
+            }
+            OptionsError::NodeGenerationFailed => write!(
+                f,
+                "cannot place all nodes without overlaps; try fewer nodes or another seed"
+            ),
+            OptionsError::ConfigIo(err) => write!(f, "cannot read config file: {}", err),
+            OptionsError::ConfigFormat(err) => write!(f, "invalid config file: {}", err),
+            OptionsError::Recipes(err) => write!(f, "{}", err),
+            OptionsError::Bonuses(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// content of a config file; missing fields keep their current values
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    num_nodes: Option<usize>,
+    width: Option<f32>,
+    height: Option<f32>,
+    starting_population: Option<usize>,
+    seed: Option<u64>,
+    recipes: Option<PathBuf>,
+    bonuses: Option<PathBuf>,
+    pruned_roads: Option<bool>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GameOptions {
+    num_nodes: usize,
+    dimension: (f32, f32),
+    starting_population: usize,
+    seed: Option<u64>,
+    recipes_file: Option<PathBuf>, // None for the built-in recipes
+    bonuses_file: Option<PathBuf>, // None for the built-in bonuses
+    pruned_roads: bool,            // relative neighbourhood graph rather than full triangulation
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        Self {
+            num_nodes: 10,
+            dimension: (300., 300.),
+            starting_population: 100,
+            seed: None,
+            recipes_file: None,
+            bonuses_file: None,
+            pruned_roads: false,
+        }
+    }
+}
+
+impl GameOptions {
+    pub fn build(self) -> Result<Game, OptionsError> {
+        self.validate()?;
+
+        let Self {
+            num_nodes,
+            dimension,
+            starting_population,
+            seed,
+            recipes_file,
+            bonuses_file,
+            pruned_roads,
+        } = self;
+
+        let recipes = match recipes_file {
+            Some(path) => Recipes::load(path).map_err(OptionsError::Recipes)?,
+            None => Recipes::default(),
+        };
+        let bonuses = match bonuses_file {
+            Some(path) => Bonuses::load(path).map_err(OptionsError::Bonuses)?,
+            None => Bonuses::default(),
+        };
+
+        let (width, height) = dimension;
+
+        let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let mut rng = GameRng::seed_from_u64(seed);
+
+        let nodes: Vec<Node> = {
+            let mut try_counter = 0;
+            let max_tries = 2 * num_nodes;
+
+            let mut coor_candidates = Vec::with_capacity(100);
+            let two_city_radius = CITY_RADIUS + CITY_RADIUS;
+            while coor_candidates.len() < num_nodes {
+                if try_counter >= max_tries {
+                    return Err(OptionsError::NodeGenerationFailed);
+                }
+
+                try_counter += 1;
+
+                let coor = {
+                    let x = rng.gen_range(two_city_radius, width - two_city_radius);
+                    let y = rng.gen_range(two_city_radius, height - two_city_radius);
+                    Point2::new(x, y)
+                };
+
+                let is_overlapping = coor_candidates
+                    .iter()
+                    .any(|other_coor| distance_squared(other_coor, &coor) < CITY_RADIUS_SQUARED);
+
+                if is_overlapping {
+                    continue;
+                }
+                coor_candidates.push(coor);
+            }
+
+            coor_candidates
+                .iter()
+                .map(|&coor| Node {
+                    coor,
+                    population: starting_population,
+                    housing: Default::default(),
+                    births: 0,
+                    deaths: 0,
+                    birth_carry: 0.,
+                    death_carry: 0.,
+                    education_progress: 0.,
+                    institutions: Default::default(),
+                    market: Default::default(),
+                    demand: Default::default(),
+                    consumed: Default::default(),
+                    price_history: Default::default(),
+                    resources: [
+                        Commodity::Grain,
+                        Commodity::Steel,
+                        Commodity::Fruit,
+                        Commodity::Meat,
+                    ],
+                    buildings: Default::default(),
+                })
+                .collect()
+        };
+
+        let coors: Vec<_> = nodes.iter().map(|node| node.coor).collect();
+        let roads = Roads::generate(&coors, pruned_roads);
+
+        Ok(Game {
+            nodes,
+            roads,
+            rng,
+            tick: 0,
+            ledger: Default::default(),
+            recipes,
+            bonuses,
+
+            entities: Default::default(),
+            nations: Default::default(),
+            corporations: Default::default(),
+
+            unit_orders: Default::default(),
+            building_grid: Default::default(),
+            building_sites: Default::default(),
+
+            unit_nodes: Default::default(),
+            unit_x_axis: Default::default(),
+            unit_y_axis: Default::default(),
+        })
+    }
+
+    pub fn set_num_nodes(self, num_nodes: usize) -> Self {
+        Self { num_nodes, ..self }
+    }
+
+    pub fn set_dimension(self, dimension: (f32, f32)) -> Self {
+        Self { dimension, ..self }
+    }
+
+    pub fn set_starting_population(self, starting_population: usize) -> Self {
+        Self {
+            starting_population,
+            ..self
+        }
+    }
+
+    pub fn set_seed(self, state: u64) -> Self {
+        Self {
+            seed: Some(state),
+            ..self
+        }
+    }
+
+    pub fn set_recipes_file<P: Into<PathBuf>>(self, path: P) -> Self {
+        Self {
+            recipes_file: Some(path.into()),
+            ..self
+        }
+    }
+
+    pub fn set_bonuses_file<P: Into<PathBuf>>(self, path: P) -> Self {
+        Self {
+            bonuses_file: Some(path.into()),
+            ..self
+        }
+    }
+
+    pub fn set_pruned_roads(self, pruned_roads: bool) -> Self {
+        Self {
+            pruned_roads,
+            ..self
+        }
+    }
+
+    /// apply settings from a TOML config file, e.g.
+    ///
+    /// ```toml
+    /// num_nodes = 15
+    /// width = 300.0
+    /// height = 300.0
+    /// starting_population = 100
+    /// seed = 1026304851583305830
+    /// recipes = "data/recipes.toml"
+    /// bonuses = "data/bonuses.toml"
+    /// pruned_roads = true
+    /// ```
+    pub fn apply_config_file<P: AsRef<Path>>(self, path: P) -> Result<Self, OptionsError> {
+        let content = fs::read_to_string(path).map_err(OptionsError::ConfigIo)?;
+        self.apply_config_str(&content)
+    }
+
+    pub fn apply_config_str(self, content: &str) -> Result<Self, OptionsError> {
+        let config: ConfigFile = toml::from_str(content).map_err(OptionsError::ConfigFormat)?;
+        let (width, height) = self.dimension;
+
+        Ok(Self {
+            num_nodes: config.num_nodes.unwrap_or(self.num_nodes),
+            dimension: (
+                config.width.unwrap_or(width),
+                config.height.unwrap_or(height),
+            ),
+            starting_population: config
+                .starting_population
+                .unwrap_or(self.starting_population),
+            seed: config.seed.or(self.seed),
+            recipes_file: config.recipes.or(self.recipes_file),
+            bonuses_file: config.bonuses.or(self.bonuses_file),
+            pruned_roads: config.pruned_roads.unwrap_or(self.pruned_roads),
+        })
+    }
+
+    /// check the options without building the game
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        let (width, height) = self.dimension;
+
+        // nodes are generated at least 2 city radii away from the edges
+        let min = 4. * CITY_RADIUS;
+
+        if self.num_nodes == 0 {
+            return Err(OptionsError::NoNodes);
+        }
+
+        let is_too_small = |length: f32| !length.is_finite() || length <= min;
+        if is_too_small(width) || is_too_small(height) {
+            return Err(OptionsError::DimensionTooSmall { min });
+        }
+
+        // rough upper bound, so that node generation doesn't run out of tries
+        let usable_area = (width - min) * (height - min);
+        let node_area = 4. * CITY_RADIUS_SQUARED;
+        let max = (usable_area / node_area) as usize;
+        if self.num_nodes > max {
+            return Err(OptionsError::TooManyNodes { max });
+        }
+
+        Ok(())
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    pub fn dimension(&self) -> (f32, f32) {
+        self.dimension
+    }
+
+    pub fn starting_population(&self) -> usize {
+        self.starting_population
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn recipes_file(&self) -> Option<&Path> {
+        self.recipes_file.as_deref()
+    }
+
+    pub fn bonuses_file(&self) -> Option<&Path> {
+        self.bonuses_file.as_deref()
+    }
+
+    pub fn pruned_roads(&self) -> bool {
+        self.pruned_roads
+    }
+}
+
+impl fmt::Display for GameOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (width, height) = self.dimension;
+        write!(
+            f,
+            "--num-nodes {} --width {} --height {} --starting-population {}",
+            self.num_nodes, width, height, self.starting_population
+        )?;
+        if let Some(seed) = self.seed {
+            write!(f, " --seed {}", seed)?;
+        }
+        if let Some(path) = &self.recipes_file {
+            write!(f, " --recipes {}", path.display())?;
+        }
+        if let Some(path) = &self.bonuses_file {
+            write!(f, " --bonuses {}", path.display())?;
+        }
+        if self.pruned_roads {
+            write!(f, " --pruned-roads")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod options_tests {
+    use super::*;
+
+    #[test]
+    fn config_overrides_only_given_fields() {
+        let options = GameOptions::default()
+            .set_seed(5)
+            .apply_config_str("num_nodes = 3\nwidth = 123.0\n")
+            .unwrap();
+
+        assert_eq!(options.num_nodes(), 3);
+        assert_eq!(options.dimension(), (123., 300.));
+        assert_eq!(options.starting_population(), 100);
+        assert_eq!(options.seed(), Some(5));
+    }
+
+    #[test]
+    fn config_rejects_unknown_fields() {
+        match GameOptions::default().apply_config_str("num_node = 3") {
+            Err(OptionsError::ConfigFormat(_)) => {}
+            _ => panic!("expected a format error"),
+        }
+    }
+
+    #[test]
+    fn recipes_file() {
+        let options = GameOptions::default().set_recipes_file("data/recipes.toml");
+        assert!(options
+            .to_string()
+            .ends_with(" --recipes data/recipes.toml"));
+        assert_eq!(options.build().unwrap().recipes, Recipes::default());
+
+        match GameOptions::default()
+            .set_recipes_file("no/such/recipes.toml")
+            .build()
+        {
+            Err(OptionsError::Recipes(RecipeError::Io(_))) => {}
+            _ => panic!("expected a recipe error"),
+        }
+    }
+
+    #[test]
+    fn bonuses_file() {
+        let options = GameOptions::default().set_bonuses_file("data/bonuses.toml");
+        assert!(options
+            .to_string()
+            .ends_with(" --bonuses data/bonuses.toml"));
+        assert_eq!(options.build().unwrap().bonuses, Bonuses::default());
+
+        match GameOptions::default()
+            .set_bonuses_file("no/such/bonuses.toml")
+            .build()
+        {
+            Err(OptionsError::Bonuses(BonusError::Io(_))) => {}
+            _ => panic!("expected a bonus error"),
+        }
+    }
+
+    #[test]
+    fn validation() {
+        assert!(GameOptions::default().validate().is_ok());
+
+        match GameOptions::default().set_num_nodes(0).validate() {
+            Err(OptionsError::NoNodes) => {}
+            _ => panic!("expected NoNodes"),
+        }
+        match GameOptions::default().set_dimension((40., 300.)).validate() {
+            Err(OptionsError::DimensionTooSmall { .. }) => {}
+            _ => panic!("expected DimensionTooSmall"),
+        }
+        match GameOptions::default().set_num_nodes(1000).validate() {
+            Err(OptionsError::TooManyNodes { .. }) => {}
+            _ => panic!("expected TooManyNodes"),
+        }
+    }
+}
@@ -0,0 +1,1095 @@
+use new::game::warehouse::WarehouseSetting;
+use std::convert::TryFrom;
+use std::fmt;
+
+/*
+Grammar of the REPL. Every command is a fixed sequence of tokens, written down in GRAMMAR below;
+both the parser and the help message are derived from that table, so adding a command is a
+matter of adding a Command variant and a Rule.
+
+The machinery is generic over what a rule builds, so that other line-based languages (the
+expectations of script mode) can reuse it with a grammar of their own.
+*/
+
+/// most ticks that a single step command may advance the game by
+pub const MAX_STEP: usize = 100_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Help,
+    Exit,
+    Step {
+        count: usize,
+    },
+    ListNodes,
+    ListUnits,
+    ListCharacters,
+    ListNations,
+    ListCorporations,
+    ListRoads,
+    ListCollisions,
+    MoveUnit {
+        character_idx: usize,
+        node_idx: usize,
+    },
+    MoveUnitToPoint {
+        character_idx: usize,
+        x: f32,
+        y: f32,
+    },
+    QueueUnit {
+        character_idx: usize,
+        node_idx: usize,
+    },
+    QueueUnitToPoint {
+        character_idx: usize,
+        x: f32,
+        y: f32,
+    },
+    PatrolUnit {
+        character_idx: usize,
+    },
+    HoldUnit {
+        character_idx: usize,
+    },
+    EstimateNearbyObjects {
+        x: f32,
+        y: f32,
+        radius: f32,
+    },
+    CreateBuilding {
+        kind: String,
+        x: u32,
+        y: u32,
+    },
+    CreateIndustry {
+        recipe: String,
+        x: u32,
+        y: u32,
+    },
+    SetRecipe {
+        building: String,
+        recipe: String,
+    },
+    DemolishAt {
+        x: u32,
+        y: u32,
+    },
+    Demolish {
+        building: String,
+    },
+    ConfigureWarehouse {
+        building: String,
+        commodity: String,
+        setting: WarehouseSetting,
+    },
+    ShowWarehouse {
+        building: String,
+    },
+    ShowBuilding {
+        building: String,
+    },
+    ShowMarket {
+        node_idx: usize,
+    },
+    SetBudget {
+        institution: String,
+        node_idx: usize,
+        rate: f32,
+    },
+    ShowNode {
+        node_idx: usize,
+    },
+    ShowHousing {
+        node_idx: usize,
+    },
+    ShowRoute {
+        character_idx: usize,
+    },
+    Audit,
+    AuditAccount {
+        account: String,
+    },
+    ListRecipes,
+    ListBuildingKinds,
+    LandColonyShip,
+    DeployCharacter {
+        character_idx: usize,
+    },
+    CharacterEnterCity {
+        character_idx: usize,
+    },
+    BuildBusiness {
+        character_idx: usize,
+        kind: String,
+        x: u32,
+        y: u32,
+    },
+    ShowCorporation {
+        corporation_idx: usize,
+    },
+    IssueShares {
+        corporation_idx: usize,
+        qty: u32,
+    },
+    TransferShares {
+        corporation_idx: usize,
+        from: usize,
+        to: usize,
+        qty: u32,
+    },
+    Save {
+        path: String,
+    },
+    Load {
+        path: String,
+    },
+}
+
+impl Command {
+    /// commands that manage the session rather than play the game; they aren't recorded
+    pub fn is_session_command(&self) -> bool {
+        matches!(
+            self,
+            Command::Help | Command::Save { .. } | Command::Load { .. }
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Token {
+    Keyword(&'static str),
+    Index(&'static str),    // non-negative integer
+    Quantity(&'static str), // non-negative integer that fits in a u32
+    Ticks(&'static str),    // non-negative integer up to MAX_STEP
+    Number(&'static str),   // real number
+    Tile(&'static str),     // (x,y) in non-negative integers
+    Point(&'static str),    // (x,y) in real numbers
+    Path(&'static str),
+    Name(&'static str), // a single word
+    Text(&'static str), // the rest of the line, at least one word
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Keyword(keyword) => write!(f, "{}", keyword),
+            Token::Index(name)
+            | Token::Quantity(name)
+            | Token::Ticks(name)
+            | Token::Number(name)
+            | Token::Path(name)
+            | Token::Name(name) => {
+                write!(f, "<{}>", name)
+            }
+            Token::Tile(name) | Token::Point(name) => write!(f, "(<{} x>,<{} y>)", name, name),
+            Token::Text(name) => write!(f, "<{}...>", name),
+        }
+    }
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Keyword(keyword) => format!("\"{}\"", keyword),
+            Token::Index(name) => format!("<{}> (non-negative integer)", name),
+            Token::Quantity(name) => format!("<{}> (non-negative integer)", name),
+            Token::Ticks(name) => format!("<{}> (at most {} ticks)", name, MAX_STEP),
+            Token::Number(name) => format!("<{}> (number)", name),
+            Token::Tile(name) => format!("<{}> (tile coordinate, e.g. (1,2))", name),
+            Token::Point(name) => format!("<{}> (coordinate, e.g. (1.5,2))", name),
+            Token::Path(name) => format!("<{}> (file path)", name),
+            Token::Name(name) => format!("<{}> (word)", name),
+            Token::Text(name) => format!("<{}> (text)", name),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Value {
+    Index(usize),
+    Quantity(u32),
+    Number(f32),
+    Tile(u32, u32),
+    Point(f32, f32),
+    Path(String),
+    Text(String),
+}
+
+/// parsed values of a rule, consumed in order by the rule's builder
+pub struct Values(std::vec::IntoIter<Value>);
+
+impl Values {
+    pub fn index(&mut self) -> usize {
+        match self.0.next() {
+            Some(Value::Index(value)) => value,
+            _ => unreachable!("bug: rule and builder disagree"),
+        }
+    }
+
+    pub fn quantity(&mut self) -> u32 {
+        match self.0.next() {
+            Some(Value::Quantity(value)) => value,
+            _ => unreachable!("bug: rule and builder disagree"),
+        }
+    }
+
+    pub fn number(&mut self) -> f32 {
+        match self.0.next() {
+            Some(Value::Number(value)) => value,
+            _ => unreachable!("bug: rule and builder disagree"),
+        }
+    }
+
+    pub fn tile(&mut self) -> (u32, u32) {
+        match self.0.next() {
+            Some(Value::Tile(x, y)) => (x, y),
+            _ => unreachable!("bug: rule and builder disagree"),
+        }
+    }
+
+    pub fn point(&mut self) -> (f32, f32) {
+        match self.0.next() {
+            Some(Value::Point(x, y)) => (x, y),
+            _ => unreachable!("bug: rule and builder disagree"),
+        }
+    }
+
+    pub fn path(&mut self) -> String {
+        match self.0.next() {
+            Some(Value::Path(value)) => value,
+            _ => unreachable!("bug: rule and builder disagree"),
+        }
+    }
+
+    pub fn text(&mut self) -> String {
+        match self.0.next() {
+            Some(Value::Text(value)) => value,
+            _ => unreachable!("bug: rule and builder disagree"),
+        }
+    }
+}
+
+pub struct Rule<T: 'static> {
+    pub tokens: &'static [Token],
+    pub description: &'static str,
+    pub build: fn(&mut Values) -> T,
+}
+
+use self::Token::*;
+
+const GRAMMAR: &[Rule<Command>] = &[
+    Rule {
+        tokens: &[Keyword("help")],
+        description: "list all commands",
+        build: |_| Command::Help,
+    },
+    Rule {
+        tokens: &[Keyword("exit")],
+        description: "quit the game",
+        build: |_| Command::Exit,
+    },
+    Rule {
+        tokens: &[Keyword("step")],
+        description: "advance the game by one tick",
+        build: |_| Command::Step { count: 1 },
+    },
+    Rule {
+        tokens: &[Keyword("step"), Ticks("count")],
+        description: "advance the game by several ticks",
+        build: |values| Command::Step {
+            count: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[Keyword("list"), Keyword("nodes")],
+        description: "list all nodes",
+        build: |_| Command::ListNodes,
+    },
+    Rule {
+        tokens: &[Keyword("list"), Keyword("units")],
+        description: "list deployed characters",
+        build: |_| Command::ListUnits,
+    },
+    Rule {
+        tokens: &[Keyword("list"), Keyword("characters")],
+        description: "list all characters",
+        build: |_| Command::ListCharacters,
+    },
+    Rule {
+        tokens: &[Keyword("list"), Keyword("nations")],
+        description: "list all nations",
+        build: |_| Command::ListNations,
+    },
+    Rule {
+        tokens: &[Keyword("list"), Keyword("corporations")],
+        description: "list all corporations",
+        build: |_| Command::ListCorporations,
+    },
+    Rule {
+        tokens: &[Keyword("list"), Keyword("roads")],
+        description: "list the roads between nodes",
+        build: |_| Command::ListRoads,
+    },
+    Rule {
+        tokens: &[Keyword("list"), Keyword("collisions")],
+        description: "list the units whose bodies overlap, and by how much",
+        build: |_| Command::ListCollisions,
+    },
+    Rule {
+        tokens: &[Keyword("list"), Keyword("recipes")],
+        description: "list production recipes",
+        build: |_| Command::ListRecipes,
+    },
+    Rule {
+        tokens: &[Keyword("list"), Keyword("building"), Keyword("kinds")],
+        description: "list the kinds of buildings and their construction rules",
+        build: |_| Command::ListBuildingKinds,
+    },
+    Rule {
+        tokens: &[
+            Keyword("move"),
+            Keyword("unit"),
+            Index("unit id"),
+            Keyword("to"),
+            Keyword("node"),
+            Index("node id"),
+        ],
+        description: "send a deployed unit to a node, along the roads, instead of its orders",
+        build: |values| Command::MoveUnit {
+            character_idx: values.index(),
+            node_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("move"),
+            Keyword("unit"),
+            Index("unit id"),
+            Keyword("to"),
+            Point("point"),
+        ],
+        description: "send a deployed unit straight to a point, instead of its orders",
+        build: |values| {
+            let character_idx = values.index();
+            let (x, y) = values.point();
+            Command::MoveUnitToPoint {
+                character_idx,
+                x,
+                y,
+            }
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("queue"),
+            Keyword("unit"),
+            Index("unit id"),
+            Keyword("to"),
+            Keyword("node"),
+            Index("node id"),
+        ],
+        description: "send a deployed unit to a node once it's done with its orders",
+        build: |values| Command::QueueUnit {
+            character_idx: values.index(),
+            node_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("queue"),
+            Keyword("unit"),
+            Index("unit id"),
+            Keyword("to"),
+            Point("point"),
+        ],
+        description: "send a deployed unit to a point once it's done with its orders",
+        build: |values| {
+            let character_idx = values.index();
+            let (x, y) = values.point();
+            Command::QueueUnitToPoint {
+                character_idx,
+                x,
+                y,
+            }
+        },
+    },
+    Rule {
+        tokens: &[Keyword("patrol"), Keyword("unit"), Index("unit id")],
+        description: "make a unit loop through its orders",
+        build: |values| Command::PatrolUnit {
+            character_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[Keyword("hold"), Keyword("unit"), Index("unit id")],
+        description: "stop a unit where it is, dropping its orders",
+        build: |values| Command::HoldUnit {
+            character_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("rough"),
+            Keyword("nearby"),
+            Keyword("objects"),
+            Point("center"),
+            Number("radius"),
+        ],
+        description: "list units around a point",
+        build: |values| {
+            let (x, y) = values.point();
+            Command::EstimateNearbyObjects {
+                x,
+                y,
+                radius: values.number(),
+            }
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("create"),
+            Name("kind"),
+            Keyword("at"),
+            Tile("top left"),
+        ],
+        description: "build a building of a kind listed by \"list building kinds\"",
+        build: |values| {
+            let kind = values.text();
+            let (x, y) = values.tile();
+            Command::CreateBuilding { kind, x, y }
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("create"),
+            Keyword("industry"),
+            Name("recipe"),
+            Keyword("at"),
+            Tile("top left"),
+        ],
+        description: "build the mine, workshop or factory running a recipe",
+        build: |values| {
+            let recipe = values.text();
+            let (x, y) = values.tile();
+            Command::CreateIndustry { recipe, x, y }
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("set"),
+            Keyword("recipe"),
+            Keyword("of"),
+            Name("building id"),
+            Keyword("to"),
+            Name("recipe"),
+        ],
+        description: "change what a mine, workshop or factory produces",
+        build: |values| Command::SetRecipe {
+            building: values.text(),
+            recipe: values.text(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("demolish"),
+            Keyword("building"),
+            Keyword("at"),
+            Tile("tile"),
+        ],
+        description: "demolish the building covering a tile",
+        build: |values| {
+            let (x, y) = values.tile();
+            Command::DemolishAt { x, y }
+        },
+    },
+    Rule {
+        tokens: &[Keyword("demolish"), Name("building id")],
+        description: "demolish a building by id, e.g. Farm(0)",
+        build: |values| Command::Demolish {
+            building: values.text(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("set"),
+            Keyword("warehouse"),
+            Name("building id"),
+            Keyword("demand"),
+            Name("commodity"),
+            Quantity("quantity"),
+        ],
+        description: "stock a commodity up to a quantity",
+        build: |values| Command::ConfigureWarehouse {
+            building: values.text(),
+            commodity: values.text(),
+            setting: WarehouseSetting::DemandQty(values.quantity()),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("set"),
+            Keyword("warehouse"),
+            Name("building id"),
+            Keyword("import"),
+            Keyword("price"),
+            Name("commodity"),
+            Number("price"),
+        ],
+        description: "buy a commodity while its price is at most this",
+        build: |values| Command::ConfigureWarehouse {
+            building: values.text(),
+            commodity: values.text(),
+            setting: WarehouseSetting::ImportPrice(values.number()),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("set"),
+            Keyword("warehouse"),
+            Name("building id"),
+            Keyword("sale"),
+            Keyword("price"),
+            Name("commodity"),
+            Number("price"),
+        ],
+        description: "sell a commodity while its price is at least this (0 to keep it)",
+        build: |values| Command::ConfigureWarehouse {
+            building: values.text(),
+            commodity: values.text(),
+            setting: WarehouseSetting::SalePrice(values.number()),
+        },
+    },
+    Rule {
+        tokens: &[Keyword("show"), Keyword("warehouse"), Name("building id")],
+        description: "show the settings, storage and trades of a warehouse",
+        build: |values| Command::ShowWarehouse {
+            building: values.text(),
+        },
+    },
+    Rule {
+        tokens: &[Keyword("show"), Keyword("building"), Name("building id")],
+        description: "show where a building stands and the bonuses of its output",
+        build: |values| Command::ShowBuilding {
+            building: values.text(),
+        },
+    },
+    Rule {
+        tokens: &[Keyword("show"), Keyword("market"), Index("node index")],
+        description: "show the stock, demand and recent prices of a node's market",
+        build: |values| Command::ShowMarket {
+            node_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("set"),
+            Keyword("budget"),
+            Keyword("of"),
+            Name("institution"),
+            Keyword("in"),
+            Keyword("node"),
+            Index("node index"),
+            Keyword("to"),
+            Number("rate"),
+        ],
+        description: "spend a share (0 to 1) of a city's cash on an institution every tick",
+        build: |values| Command::SetBudget {
+            institution: values.text(),
+            node_idx: values.index(),
+            rate: values.number(),
+        },
+    },
+    Rule {
+        tokens: &[Keyword("show"), Keyword("node"), Index("node index")],
+        description: "show the population, crime, militia and institutions of a node",
+        build: |values| Command::ShowNode {
+            node_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[Keyword("show"), Keyword("housing"), Index("node index")],
+        description: "show the households of a node and how many people live in them",
+        build: |values| Command::ShowHousing {
+            node_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[Keyword("show"), Keyword("route"), Index("character id")],
+        description: "show the nodes that a unit still has to pass through, and when it arrives",
+        build: |values| Command::ShowRoute {
+            character_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[Keyword("audit")],
+        description: "show every balance and check them against the transactions",
+        build: |_| Command::Audit,
+    },
+    Rule {
+        tokens: &[Keyword("audit"), Name("account")],
+        description: "show the latest transactions of an account, e.g. Nation(0) or Warehouse(0)",
+        build: |values| Command::AuditAccount {
+            account: values.text(),
+        },
+    },
+    Rule {
+        tokens: &[Keyword("land"), Keyword("colony"), Keyword("ship")],
+        description: "found a nation on an uncolonized node",
+        build: |_| Command::LandColonyShip,
+    },
+    Rule {
+        tokens: &[
+            Keyword("deploy"),
+            Keyword("character"),
+            Index("character id"),
+        ],
+        description: "turn a parked character into a unit",
+        build: |values| Command::DeployCharacter {
+            character_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("character"),
+            Index("character id"),
+            Keyword("enter"),
+            Keyword("city"),
+        ],
+        description: "(TODO) same as deploy character",
+        build: |values| Command::CharacterEnterCity {
+            character_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("character"),
+            Index("character id"),
+            Keyword("build"),
+            Name("kind"),
+            Keyword("at"),
+            Tile("top left"),
+        ],
+        description:
+            "build a business or a corp-hq for the character's corporation, founding it if needed",
+        build: |values| {
+            let character_idx = values.index();
+            let kind = values.text();
+            let (x, y) = values.tile();
+            Command::BuildBusiness {
+                character_idx,
+                kind,
+                x,
+                y,
+            }
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("show"),
+            Keyword("corporation"),
+            Index("corporation index"),
+        ],
+        description: "show the shareholders and buildings of a corporation",
+        build: |values| Command::ShowCorporation {
+            corporation_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("issue"),
+            Quantity("quantity"),
+            Keyword("shares"),
+            Keyword("of"),
+            Keyword("corporation"),
+            Index("corporation index"),
+        ],
+        description: "issue new shares to the CEO of a corporation",
+        build: |values| {
+            let qty = values.quantity();
+            Command::IssueShares {
+                corporation_idx: values.index(),
+                qty,
+            }
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("transfer"),
+            Quantity("quantity"),
+            Keyword("shares"),
+            Keyword("of"),
+            Keyword("corporation"),
+            Index("corporation index"),
+            Keyword("from"),
+            Keyword("character"),
+            Index("character id"),
+            Keyword("to"),
+            Keyword("character"),
+            Index("character id"),
+        ],
+        description: "give shares of a corporation to another character",
+        build: |values| {
+            let qty = values.quantity();
+            Command::TransferShares {
+                corporation_idx: values.index(),
+                from: values.index(),
+                to: values.index(),
+                qty,
+            }
+        },
+    },
+    Rule {
+        tokens: &[Keyword("save"), Path("file")],
+        description: "save the game",
+        build: |values| Command::Save {
+            path: values.path(),
+        },
+    },
+    Rule {
+        tokens: &[Keyword("load"), Path("file")],
+        description: "load a saved game",
+        build: |values| Command::Load {
+            path: values.path(),
+        },
+    },
+];
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub column: usize,         // 1-based, where the offending token starts
+    pub expected: Vec<String>, // alternatives that would have been accepted
+    pub found: Option<String>, // None for end of input
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: expected ", self.column)?;
+        match self.expected.len() {
+            1 => write!(f, "{}", self.expected[0])?,
+            _ => write!(f, "one of {}", self.expected.join(", "))?,
+        }
+        match &self.found {
+            Some(found) => write!(f, ", found \"{}\"", found)?,
+            None => write!(f, ", found end of input")?,
+        }
+        if let Some(reason) = &self.reason {
+            write!(f, " ({})", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// split by whitespace, remembering the 1-based column where each word starts
+fn tokenize(line: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for (idx, c) in line.char_indices() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some(idx),
+            (true, Some(begin)) => {
+                words.push((begin, &line[begin..idx]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        words.push((begin, &line[begin..]));
+    }
+
+    words
+        .into_iter()
+        .map(|(begin, word)| (line[..begin].chars().count() + 1, word))
+        .collect()
+}
+
+fn parse_pair(word: &str) -> Option<(&str, &str)> {
+    if word.starts_with('(') && word.ends_with(')') && word.len() >= 2 {
+        let mut parts = word[1..word.len() - 1].splitn(2, ',');
+        match (parts.next(), parts.next()) {
+            (Some(x), Some(y)) => Some((x, y)),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+fn parse_index(word: &str) -> Result<usize, &'static str> {
+    if word.is_empty() || !word.chars().all(|c| c.is_ascii_digit()) {
+        Err("not a non-negative integer")
+    } else {
+        word.parse().map_err(|_| "number too large")
+    }
+}
+
+fn parse_number(word: &str) -> Result<f32, &'static str> {
+    match word.parse::<f32>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        _ => Err("not a number"),
+    }
+}
+
+/// result of matching a token against a word; Err holds the reason for a rejected value
+fn match_token(token: Token, word: &str) -> Result<Option<Value>, Option<&'static str>> {
+    match token {
+        Keyword(keyword) => {
+            if keyword == word {
+                Ok(None)
+            } else {
+                Err(None)
+            }
+        }
+        Index(_) => parse_index(word)
+            .map(|v| Some(Value::Index(v)))
+            .map_err(Some),
+        Quantity(_) => {
+            let value = parse_index(word).map_err(Some)?;
+            let value = u32::try_from(value).map_err(|_| Some("number too large"))?;
+            Ok(Some(Value::Quantity(value)))
+        }
+        Ticks(_) => match parse_index(word).map_err(Some)? {
+            value if value <= MAX_STEP => Ok(Some(Value::Index(value))),
+            _ => Err(Some("too many ticks")),
+        },
+        Number(_) => parse_number(word)
+            .map(|v| Some(Value::Number(v)))
+            .map_err(Some),
+        Tile(_) => {
+            let (x, y) = parse_pair(word).ok_or(Some("not a pair"))?;
+            let to_u32 = |value| u32::try_from(value).map_err(|_| Some("number too large"));
+            let x = to_u32(parse_index(x).map_err(Some)?)?;
+            let y = to_u32(parse_index(y).map_err(Some)?)?;
+            Ok(Some(Value::Tile(x, y)))
+        }
+        Point(_) => {
+            let (x, y) = parse_pair(word).ok_or(Some("not a pair"))?;
+            let x = parse_number(x).map_err(Some)?;
+            let y = parse_number(y).map_err(Some)?;
+            Ok(Some(Value::Point(x, y)))
+        }
+        Path(_) => Ok(Some(Value::Path(word.to_owned()))),
+        Name(_) | Text(_) => Ok(Some(Value::Text(word.to_owned()))),
+    }
+}
+
+/// try to match a rule; on failure, return how many words were matched and the error
+fn match_rule<T>(
+    rule: &Rule<T>,
+    words: &[(usize, &str)],
+) -> Result<T, (usize, String, Option<String>)> {
+    let mut values = Vec::new();
+
+    for (idx, &token) in rule.tokens.iter().enumerate() {
+        let word = match (token, words.get(idx)) {
+            (Text(_), Some(_)) => {
+                let rest: Vec<_> = words[idx..].iter().map(|&(_, word)| word).collect();
+                values.push(Value::Text(rest.join(" ")));
+                return Ok((rule.build)(&mut Values(values.into_iter())));
+            }
+            (_, Some((_, word))) => word,
+            (_, None) => return Err((idx, token.describe(), None)),
+        };
+
+        match match_token(token, word) {
+            Ok(Some(value)) => values.push(value),
+            Ok(None) => {}
+            Err(reason) => return Err((idx, token.describe(), reason.map(str::to_owned))),
+        }
+    }
+
+    if words.len() > rule.tokens.len() {
+        return Err((rule.tokens.len(), "end of input".to_owned(), None));
+    }
+
+    Ok((rule.build)(&mut Values(values.into_iter())))
+}
+
+pub fn parse(line: &str) -> Result<Command, ParseError> {
+    parse_with(GRAMMAR, line)
+}
+
+/// parse a line against any grammar; the first matching rule wins
+pub fn parse_with<T>(grammar: &[Rule<T>], line: &str) -> Result<T, ParseError> {
+    let words = tokenize(line);
+
+    // report the error of the rules that got furthest
+    let mut furthest = 0;
+    let mut expected: Vec<String> = Vec::new();
+    let mut reason = None;
+
+    for rule in grammar {
+        match match_rule(rule, &words) {
+            Ok(command) => return Ok(command),
+            Err((idx, description, rule_reason)) => {
+                if idx > furthest {
+                    furthest = idx;
+                    expected.clear();
+                    reason = None;
+                }
+                if idx == furthest {
+                    if !expected.contains(&description) {
+                        expected.push(description);
+                    }
+                    if reason.is_none() {
+                        reason = rule_reason;
+                    }
+                }
+            }
+        }
+    }
+
+    let (column, found) = match words.get(furthest) {
+        Some(&(column, word)) => (column, Some(word.to_owned())),
+        None => (line.chars().count() + 1, None),
+    };
+
+    Err(ParseError {
+        column,
+        expected,
+        found,
+        reason,
+    })
+}
+
+/// one line per command, generated from the grammar
+pub fn help() -> String {
+    help_with(GRAMMAR)
+}
+
+/// one line per rule of any grammar
+pub fn help_with<T>(grammar: &[Rule<T>]) -> String {
+    let usages: Vec<String> = grammar
+        .iter()
+        .map(|rule| {
+            let tokens: Vec<_> = rule.tokens.iter().map(ToString::to_string).collect();
+            tokens.join(" ")
+        })
+        .collect();
+
+    let width = usages.iter().map(String::len).max().unwrap_or(0);
+
+    grammar
+        .iter()
+        .zip(usages.iter())
+        .map(|(rule, usage)| format!("{:<width$}  {}\n", usage, rule.description, width = width))
+        .collect()
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use super::*;
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(parse("step"), Ok(Command::Step { count: 1 }));
+        assert_eq!(parse("step 30"), Ok(Command::Step { count: 30 }));
+        assert_eq!(parse("  list   units "), Ok(Command::ListUnits));
+        assert_eq!(
+            parse("move unit 3 to node 4"),
+            Ok(Command::MoveUnit {
+                character_idx: 3,
+                node_idx: 4
+            })
+        );
+        assert_eq!(
+            parse("queue unit 3 to (1.5,2)"),
+            Ok(Command::QueueUnitToPoint {
+                character_idx: 3,
+                x: 1.5,
+                y: 2.
+            })
+        );
+        assert_eq!(
+            parse("create farm at (12,34)"),
+            Ok(Command::CreateBuilding {
+                kind: "farm".to_owned(),
+                x: 12,
+                y: 34
+            })
+        );
+        assert_eq!(
+            parse("create industry glass at (5,6)"),
+            Ok(Command::CreateIndustry {
+                recipe: "glass".to_owned(),
+                x: 5,
+                y: 6
+            })
+        );
+        assert_eq!(
+            parse("rough nearby objects (1.5,-2) 3"),
+            Ok(Command::EstimateNearbyObjects {
+                x: 1.5,
+                y: -2.,
+                radius: 3.
+            })
+        );
+    }
+
+    #[test]
+    fn out_of_range_integer() {
+        let err = parse("move unit 99999999999999999999 to node 1").unwrap_err();
+        assert_eq!(err.column, 11);
+        assert_eq!(err.found, Some("99999999999999999999".to_owned()));
+        assert_eq!(err.reason, Some("number too large".to_owned()));
+
+        let err = parse("issue 4294967296 shares of corporation 0").unwrap_err();
+        assert_eq!(err.column, 7);
+        assert_eq!(err.reason, Some("number too large".to_owned()));
+    }
+
+    #[test]
+    fn step_is_capped() {
+        let max = format!("step {}", MAX_STEP);
+        assert_eq!(parse(&max), Ok(Command::Step { count: MAX_STEP }));
+
+        let err = parse(&format!("step {}", MAX_STEP + 1)).unwrap_err();
+        assert_eq!(err.column, 6);
+        assert_eq!(err.reason, Some("too many ticks".to_owned()));
+        let err = parse("step 4000000000").unwrap_err();
+        assert_eq!(err.reason, Some("too many ticks".to_owned()));
+    }
+
+    #[test]
+    fn alternatives_at_furthest_token() {
+        let err = parse("list bananas").unwrap_err();
+        assert_eq!(err.column, 6);
+        assert_eq!(err.expected.len(), 9);
+
+        let err = parse("create farm at").unwrap_err();
+        assert_eq!(err.column, 15);
+        assert_eq!(err.found, None);
+
+        let err = parse("exit now").unwrap_err();
+        assert_eq!(err.expected, vec!["end of input".to_owned()]);
+    }
+
+    #[test]
+    fn negative_tile() {
+        let err = parse("create warehouse at (-1,2)").unwrap_err();
+        assert_eq!(err.column, 21);
+        assert!(err.reason.is_some());
+    }
+
+    #[test]
+    fn text_takes_the_rest_of_the_line() {
+        const NOTES: &[Rule<String>] = &[Rule {
+            tokens: &[Keyword("note"), Text("text")],
+            description: "take a note",
+            build: |values| values.text(),
+        }];
+        assert_eq!(
+            parse_with(NOTES, "note  buy   more farms"),
+            Ok("buy more farms".to_owned())
+        );
+        assert_eq!(parse_with(NOTES, "note").unwrap_err().column, 5);
+    }
+
+    #[test]
+    fn help_lists_every_rule() {
+        assert_eq!(help().lines().count(), GRAMMAR.len());
+    }
+}
@@ -0,0 +1,209 @@
+use new::Game;
+use new::GameOptions;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/*
+Replay files are plain text, so they can be diffed and edited by hand:
+
+    replay 1
+    seed 1026304851583305830
+    num_nodes 15
+    dimension 300 300
+    starting_population 100
+    recipes data/recipes.toml
+    bonuses data/bonuses.toml
+    pruned_roads
+    > land colony ship
+    = 5d3c0f8f61b4ae0e
+    > step
+    = 0a8e51c7e37d1a20
+
+- the header describes the options used to generate the world; "recipes" and "bonuses" are only
+  written for custom data files, which have to be available when replaying, and "pruned_roads"
+  only when the road network was pruned
+- "> " lines are the accepted commands, in order
+- "= " lines (optional) are the state checksums right after the previous command
+*/
+
+const REPLAY_FORMAT_VERSION: u32 = 1;
+
+pub struct ReplayStep {
+    pub command: String,
+    pub checksum: Option<u64>,
+}
+
+pub struct Replay {
+    pub options: GameOptions,
+    pub steps: Vec<ReplayStep>,
+}
+
+pub struct Recorder {
+    writer: BufWriter<File>,
+    with_checksum: bool,
+}
+
+impl Recorder {
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        options: &GameOptions,
+        seed: u64,
+        with_checksum: bool,
+    ) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        let (width, height) = options.dimension();
+
+        writeln!(writer, "replay {}", REPLAY_FORMAT_VERSION)?;
+        writeln!(writer, "seed {}", seed)?;
+        writeln!(writer, "num_nodes {}", options.num_nodes())?;
+        writeln!(writer, "dimension {} {}", width, height)?;
+        writeln!(
+            writer,
+            "starting_population {}",
+            options.starting_population()
+        )?;
+        if let Some(path) = options.recipes_file() {
+            writeln!(writer, "recipes {}", path.display())?;
+        }
+        if let Some(path) = options.bonuses_file() {
+            writeln!(writer, "bonuses {}", path.display())?;
+        }
+        if options.pruned_roads() {
+            writeln!(writer, "pruned_roads")?;
+        }
+        writer.flush()?;
+
+        Ok(Self {
+            writer,
+            with_checksum,
+        })
+    }
+
+    /// record a command that has been executed on g
+    pub fn record(&mut self, g: &Game, command: &str) -> io::Result<()> {
+        writeln!(self.writer, "> {}", command)?;
+        if self.with_checksum {
+            writeln!(self.writer, "= {:016x}", g.checksum())?;
+        }
+
+        // flush eagerly so that the log survives crashes, which are what we want to reproduce
+        self.writer.flush()
+    }
+}
+
+fn parse_error(line_number: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", line_number, message),
+    )
+}
+
+impl Replay {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+
+        let mut options = GameOptions::default();
+        let mut steps: Vec<ReplayStep> = Vec::new();
+        let mut has_seed = false;
+
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line_number = idx + 1;
+
+            if idx == 0 {
+                let version = line
+                    .trim()
+                    .strip_prefix("replay ")
+                    .and_then(|version| version.parse::<u32>().ok())
+                    .ok_or_else(|| parse_error(line_number, "not a replay file"))?;
+
+                if version != REPLAY_FORMAT_VERSION {
+                    return Err(parse_error(
+                        line_number,
+                        &format!("unsupported replay format version {}", version),
+                    ));
+                }
+                continue;
+            }
+
+            if let Some(command) = line.strip_prefix("> ") {
+                steps.push(ReplayStep {
+                    command: command.to_owned(),
+                    checksum: None,
+                });
+                continue;
+            }
+
+            if let Some(checksum) = line.strip_prefix("= ") {
+                let checksum = u64::from_str_radix(checksum.trim(), 16)
+                    .map_err(|_| parse_error(line_number, "invalid checksum"))?;
+                match steps.last_mut() {
+                    Some(step) => step.checksum = Some(checksum),
+                    None => return Err(parse_error(line_number, "checksum without a command")),
+                }
+                continue;
+            }
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if !steps.is_empty() {
+                return Err(parse_error(line_number, "unexpected line after commands"));
+            }
+
+            let invalid_value = || parse_error(line_number, "invalid value");
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("seed") => {
+                    let seed = tokens.next().and_then(|s| s.parse().ok());
+                    options = options.set_seed(seed.ok_or_else(invalid_value)?);
+                    has_seed = true;
+                }
+                Some("num_nodes") => {
+                    let num_nodes = tokens.next().and_then(|s| s.parse().ok());
+                    options = options.set_num_nodes(num_nodes.ok_or_else(invalid_value)?);
+                }
+                Some("dimension") => {
+                    let width = tokens.next().and_then(|s| s.parse().ok());
+                    let height = tokens.next().and_then(|s| s.parse().ok());
+                    options = options.set_dimension((
+                        width.ok_or_else(invalid_value)?,
+                        height.ok_or_else(invalid_value)?,
+                    ));
+                }
+                Some("starting_population") => {
+                    let starting_population = tokens.next().and_then(|s| s.parse().ok());
+                    options = options
+                        .set_starting_population(starting_population.ok_or_else(invalid_value)?);
+                }
+                Some("recipes") => {
+                    let path = line.trim_start()["recipes".len()..].trim();
+                    if path.is_empty() {
+                        return Err(invalid_value());
+                    }
+                    options = options.set_recipes_file(path);
+                }
+                Some("bonuses") => {
+                    let path = line.trim_start()["bonuses".len()..].trim();
+                    if path.is_empty() {
+                        return Err(invalid_value());
+                    }
+                    options = options.set_bonuses_file(path);
+                }
+                Some("pruned_roads") => {
+                    options = options.set_pruned_roads(true);
+                }
+                _ => return Err(parse_error(line_number, "unknown header field")),
+            }
+        }
+
+        if !has_seed {
+            return Err(parse_error(1, "replay doesn't record a seed"));
+        }
+
+        Ok(Self { options, steps })
+    }
+}
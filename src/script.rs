@@ -0,0 +1,324 @@
+use crate::command::execute;
+use crate::parser::{self, Rule, Token::*};
+use new::game::CharacterMobility;
+use new::game::Commodity;
+use new::Game;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/*
+Scripts drive a game without reading stdin, and check its state along the way:
+
+    # comments and blank lines are ignored
+    land colony ship
+    expect ok
+    deploy character 0
+    move unit 0 to node 3
+    step 40
+    expect unit 0 parked at node 3
+    move unit 99 to node 0
+    expect error no character 99
+
+- every other line is a command, echoed as "> command" before its output
+- "expect" lines check the outcome of the previous command, or the current state of the game
+- a command that doesn't parse fails the script, unless the next line expects an error
+- the script stops at the first failed expectation
+*/
+
+#[derive(Debug, PartialEq)]
+enum Expectation {
+    Ok,
+    Error {
+        message: Option<String>, // substring of the error message
+    },
+    UnitAt {
+        character_idx: usize,
+        x: f32,
+        y: f32,
+    },
+    UnitParked {
+        character_idx: usize,
+        node_idx: usize,
+    },
+    BuildingAt {
+        tile: (u32, u32),
+        building: String, // as printed, e.g. Farm(0)
+    },
+    NoBuildingAt {
+        tile: (u32, u32),
+    },
+    MarketHas {
+        node_idx: usize,
+        qty: u32,
+        commodity: String,
+    },
+}
+
+const EXPECTATIONS: &[Rule<Expectation>] = &[
+    Rule {
+        tokens: &[Keyword("expect"), Keyword("ok")],
+        description: "the previous command was accepted",
+        build: |_| Expectation::Ok,
+    },
+    Rule {
+        tokens: &[Keyword("expect"), Keyword("error")],
+        description: "the previous command was rejected",
+        build: |_| Expectation::Error { message: None },
+    },
+    Rule {
+        tokens: &[Keyword("expect"), Keyword("error"), Text("message")],
+        description: "the previous command was rejected with a message containing the text",
+        build: |values| Expectation::Error {
+            message: Some(values.text()),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("expect"),
+            Keyword("unit"),
+            Index("unit id"),
+            Keyword("at"),
+            Point("coor"),
+        ],
+        description: "a unit is deployed at the coordinate, to two decimals",
+        build: |values| {
+            let character_idx = values.index();
+            let (x, y) = values.point();
+            Expectation::UnitAt {
+                character_idx,
+                x,
+                y,
+            }
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("expect"),
+            Keyword("unit"),
+            Index("unit id"),
+            Keyword("parked"),
+            Keyword("at"),
+            Keyword("node"),
+            Index("node id"),
+        ],
+        description: "a character is parked in a node",
+        build: |values| Expectation::UnitParked {
+            character_idx: values.index(),
+            node_idx: values.index(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("expect"),
+            Keyword("building"),
+            Keyword("at"),
+            Tile("tile"),
+            Keyword("is"),
+            Text("building id"),
+        ],
+        description: "a tile is occupied by the building",
+        build: |values| Expectation::BuildingAt {
+            tile: values.tile(),
+            building: values.text(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("expect"),
+            Keyword("no"),
+            Keyword("building"),
+            Keyword("at"),
+            Tile("tile"),
+        ],
+        description: "a tile is free",
+        build: |values| Expectation::NoBuildingAt {
+            tile: values.tile(),
+        },
+    },
+    Rule {
+        tokens: &[
+            Keyword("expect"),
+            Keyword("node"),
+            Index("node id"),
+            Keyword("has"),
+            Index("quantity"),
+            Name("commodity"),
+        ],
+        description: "the market of a node holds exactly that much of a commodity",
+        build: |values| Expectation::MarketHas {
+            node_idx: values.index(),
+            qty: values.index() as u32,
+            commodity: values.text(),
+        },
+    },
+];
+
+/// unit positions are compared as they are printed, with two decimals
+const POSITION_TOLERANCE: f32 = 0.005;
+
+impl Expectation {
+    /// check against the game and the outcome of the previous command; Err describes the mismatch
+    fn check(&self, g: &Game, outcome: Option<&Result<(), String>>) -> Result<(), String> {
+        match self {
+            Expectation::Ok => match outcome {
+                Some(Ok(())) => Ok(()),
+                Some(Err(err)) => Err(format!("the command was rejected: {}", err)),
+                None => Err("no command has been run".to_owned()),
+            },
+            Expectation::Error { message } => match (outcome, message) {
+                (Some(Err(_)), None) => Ok(()),
+                (Some(Err(err)), Some(message)) if err.contains(message.as_str()) => Ok(()),
+                (Some(Err(err)), Some(_)) => Err(format!("the command was rejected with: {}", err)),
+                (Some(Ok(())), _) => Err("the command was accepted".to_owned()),
+                (None, _) => Err("no command has been run".to_owned()),
+            },
+            Expectation::UnitAt {
+                character_idx,
+                x,
+                y,
+            } => {
+                let coor = g
+                    .get_unit_coor(*character_idx)
+                    .map_err(|err| err.to_string())?;
+                if (coor.x - x).abs() <= POSITION_TOLERANCE
+                    && (coor.y - y).abs() <= POSITION_TOLERANCE
+                {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "unit {} is at ({:.2},{:.2})",
+                        character_idx, coor.x, coor.y
+                    ))
+                }
+            }
+            Expectation::UnitParked {
+                character_idx,
+                node_idx,
+            } => match g
+                .entities
+                .characters
+                .get(*character_idx)
+                .map(|c| &c.mobility)
+            {
+                Some(CharacterMobility::Parked { node_idx: parked }) if parked == node_idx => {
+                    Ok(())
+                }
+                Some(CharacterMobility::Parked { node_idx: parked }) => Err(format!(
+                    "character {} is parked at node {}",
+                    character_idx, parked
+                )),
+                Some(CharacterMobility::Unit { body, .. }) => Err(format!(
+                    "character {} is deployed at ({:.2},{:.2})",
+                    character_idx, body.center.x, body.center.y
+                )),
+                None => Err(format!("no character {}", character_idx)),
+            },
+            Expectation::BuildingAt {
+                tile: (x, y),
+                building,
+            } => match g.building_grid.get(&(*x, *y)) {
+                Some(id) if format!("{:?}", id) == *building => Ok(()),
+                Some(id) => Err(format!("tile ({},{}) is occupied by {:?}", x, y, id)),
+                None => Err(format!("tile ({},{}) is free", x, y)),
+            },
+            Expectation::NoBuildingAt { tile: (x, y) } => match g.building_grid.get(&(*x, *y)) {
+                Some(id) => Err(format!("tile ({},{}) is occupied by {:?}", x, y, id)),
+                None => Ok(()),
+            },
+            Expectation::MarketHas {
+                node_idx,
+                qty,
+                commodity,
+            } => {
+                let node = g
+                    .nodes
+                    .get(*node_idx)
+                    .ok_or_else(|| format!("no node {}", node_idx))?;
+                let commodity = Commodity::from_name(commodity)
+                    .ok_or_else(|| format!("no commodity {}", commodity))?;
+                match node.market[commodity] {
+                    actual if actual == *qty => Ok(()),
+                    actual => Err(format!("node {} has {} {:?}", node_idx, actual, commodity)),
+                }
+            }
+        }
+    }
+}
+
+/// run a script on g; returns false at the first failed expectation
+pub fn run_script<P: AsRef<Path>>(g: &mut Game, path: P) -> io::Result<bool> {
+    let script = fs::read_to_string(path)?;
+
+    // outcome of the latest command, and whether it was a parse error nobody expected yet
+    let mut outcome: Option<Result<(), String>> = None;
+    let mut unexpected_parse_error: Option<(usize, &str)> = None;
+
+    let fail = |line_number: usize, line: &str, reason: &str| {
+        println!("line {}: {}: {}", line_number, line, reason);
+        Ok(false)
+    };
+
+    for (idx, line) in script.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.split_whitespace().next() == Some("expect") {
+            let expectation = match parser::parse_with(EXPECTATIONS, line) {
+                Ok(expectation) => expectation,
+                Err(err) => {
+                    return fail(line_number, line, &format!("invalid expectation: {}", err))
+                }
+            };
+            if let Some((command_line_number, command)) = unexpected_parse_error.take() {
+                if !matches!(expectation, Expectation::Error { .. }) {
+                    return fail(command_line_number, command, "invalid command");
+                }
+            }
+            if let Err(reason) = expectation.check(g, outcome.as_ref()) {
+                return fail(
+                    line_number,
+                    line,
+                    &format!("expectation failed: {}", reason),
+                );
+            }
+            continue;
+        }
+
+        if let Some((command_line_number, command)) = unexpected_parse_error {
+            return fail(command_line_number, command, "invalid command");
+        }
+
+        println!("> {}", line);
+
+        let command = match parser::parse(line) {
+            Ok(command) => command,
+            Err(err) => {
+                let message = format!("invalid command: {}", err);
+                println!("{}", message);
+                outcome = Some(Err(message));
+                unexpected_parse_error = Some((line_number, line));
+                continue;
+            }
+        };
+
+        match execute(g, command) {
+            Ok(true) => break,
+            Ok(false) => outcome = Some(Ok(())),
+            Err(err) => {
+                println!("{}", err);
+                outcome = Some(Err(err));
+            }
+        }
+    }
+
+    if let Some((command_line_number, command)) = unexpected_parse_error {
+        return fail(command_line_number, command, "invalid command");
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,109 @@
+extern crate new;
+
+use new::game::CharacterMobility;
+use new::GameError;
+use new::GameOptions;
+
+#[test]
+fn colonize_deploy_and_travel() {
+    let mut g = GameOptions::default().set_seed(7).build().unwrap();
+
+    assert!(g.colonize().is_ok());
+    assert_eq!(g.nations.len(), 1);
+
+    let home = match g.entities.characters[0].mobility {
+        CharacterMobility::Parked { node_idx } => node_idx,
+        _ => panic!("colonists start parked"),
+    };
+    let target = (home + 1) % g.nodes.len();
+
+    g.deploy_character(0).unwrap();
+    assert_eq!(g.estimate_nearby_units(g.nodes[home].coor, 1.).len(), 1);
+
+    g.set_unit_destination(0, target).unwrap();
+    for _ in 0..100 {
+        g.update_state();
+    }
+
+    match g.entities.characters[0].mobility {
+        CharacterMobility::Parked { node_idx } => assert_eq!(node_idx, target),
+        _ => panic!("unit should have arrived"),
+    }
+    assert!(g.unit_orders.is_empty());
+}
+
+#[test]
+fn rejected_operations_report_errors() {
+    let mut g = GameOptions::default()
+        .set_num_nodes(2)
+        .set_seed(7)
+        .build()
+        .unwrap();
+
+    g.colonize().unwrap();
+    g.colonize().unwrap();
+    assert_eq!(g.colonize(), Err(GameError::NoUncolonizedNode));
+
+    assert_eq!(
+        g.set_unit_destination(0, 1),
+        Err(GameError::CharacterNotDeployed(0))
+    );
+    assert_eq!(
+        g.set_unit_destination(100, 1),
+        Err(GameError::InvalidCharacterIndex(100))
+    );
+    assert_eq!(g.get_unit_coor(0), Err(GameError::CharacterNotDeployed(0)));
+
+    g.deploy_character(0).unwrap();
+    assert_eq!(g.deploy_character(0), Err(GameError::CharacterNotParked(0)));
+    assert_eq!(
+        g.set_unit_destination(0, 5),
+        Err(GameError::InvalidNodeIndex(5))
+    );
+
+    let coor = g.nodes[0].coor;
+    let top_left = [coor.x as u32 + 2, coor.y as u32 + 2];
+    let farm = g.create_farm(top_left).unwrap();
+    match g.create_warehouse(top_left) {
+        Err(GameError::TileOccupied { building, .. }) => assert_eq!(building, farm),
+        other => panic!("unexpected {:?}", other),
+    }
+    match g.create_farm([0, 0]) {
+        Err(GameError::TileOutsideNode { .. }) => {}
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn demolition_frees_tiles_and_invalidates_ids() {
+    let mut g = GameOptions::default()
+        .set_num_nodes(1)
+        .set_seed(7)
+        .build()
+        .unwrap();
+    let landing = g.colonize().unwrap();
+
+    let coor = g.nodes[0].coor;
+    let top_left = [coor.x as u32 + 2, coor.y as u32 + 2];
+    let farm = g.create_farm(top_left).unwrap();
+
+    assert_eq!(g.demolish_at((top_left[0] + 1, top_left[1] + 1)), Ok(farm));
+    assert!(!g.nodes[0].buildings.contains(&farm));
+    assert_eq!(
+        g.demolish_at((top_left[0], top_left[1])),
+        Err(GameError::NoBuildingAt {
+            tile: (top_left[0], top_left[1])
+        })
+    );
+
+    // the new farm reuses the slot, but the old id doesn't alias it
+    let new_farm = g.create_farm(top_left).unwrap();
+    assert_ne!(new_farm, farm);
+    assert_eq!(g.demolish(farm), Err(GameError::NoSuchBuilding(farm)));
+    assert_eq!(g.building_grid[&(top_left[0], top_left[1])], new_farm);
+
+    assert_eq!(
+        g.demolish(landing),
+        Err(GameError::NotDemolishable(landing))
+    );
+}
@@ -0,0 +1,151 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/*
+Every tests/scripts/<name>.script is run with a fixed seed, must pass its own expectations, and
+must print exactly tests/scripts/<name>.stdout. After an intended change of output, regenerate
+the golden files with
+
+    UPDATE_GOLDEN=1 cargo test --test scripts
+*/
+
+const SEED: &str = "1";
+
+fn run_script(script: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_new"))
+        .args(["--seed", SEED, "--script"])
+        .arg(script)
+        .output()
+        .expect("cannot run the game")
+}
+
+fn check_golden(name: &str) {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/scripts");
+    let script = dir.join(format!("{}.script", name));
+    let golden = dir.join(format!("{}.stdout", name));
+
+    let output = run_script(&script);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "script failed:\n{}", stdout);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden, &stdout).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&golden).unwrap();
+    assert!(
+        stdout == expected,
+        "output of {} differs from {}:\n{}",
+        script.display(),
+        golden.display(),
+        stdout
+    );
+}
+
+#[test]
+fn travel() {
+    check_golden("travel");
+}
+
+#[test]
+fn buildings() {
+    check_golden("buildings");
+}
+
+#[test]
+fn errors() {
+    check_golden("errors");
+}
+
+#[test]
+fn industry() {
+    check_golden("industry");
+}
+
+#[test]
+fn trading() {
+    check_golden("trading");
+}
+
+#[test]
+fn corporations() {
+    check_golden("corporations");
+}
+
+#[test]
+fn population() {
+    check_golden("population");
+}
+
+#[test]
+fn institutions() {
+    check_golden("institutions");
+}
+
+#[test]
+fn housing() {
+    check_golden("housing");
+}
+
+#[test]
+fn bonuses() {
+    check_golden("bonuses");
+}
+
+#[test]
+fn roads() {
+    check_golden("roads");
+}
+
+#[test]
+fn routes() {
+    check_golden("routes");
+}
+
+#[test]
+fn orders() {
+    check_golden("orders");
+}
+
+#[test]
+fn collisions() {
+    check_golden("collisions");
+}
+
+#[test]
+fn demolition() {
+    check_golden("demolition");
+}
+
+#[test]
+fn failed_expectation_exits_with_error() {
+    let script = env::temp_dir().join(format!("failing-{}.script", std::process::id()));
+    fs::write(&script, "land colony ship\nexpect error\nstep\n").unwrap();
+
+    let output = run_script(&script);
+    fs::remove_file(&script).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!output.status.success());
+    assert!(stdout.contains("line 2: expect error: expectation failed"));
+    assert!(
+        !stdout.contains("> step"),
+        "the script should stop at the failure"
+    );
+}
+
+#[test]
+fn unexpected_invalid_command_exits_with_error() {
+    let script = env::temp_dir().join(format!("invalid-{}.script", std::process::id()));
+    fs::write(&script, "fly to the moon\nexpect ok\n").unwrap();
+
+    let output = run_script(&script);
+    fs::remove_file(&script).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!output.status.success());
+    assert!(stdout.contains("line 1: fly to the moon: invalid command"));
+}